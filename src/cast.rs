@@ -0,0 +1,210 @@
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Where to write the asciicast recording, when set
+pub const CAST_VAR: &str = "AOC_CAST";
+
+/// Frames per second for live playback and recorded timestamps; unset runs flat out
+pub const FPS_VAR: &str = "AOC_FPS";
+
+/// Any value turns live playback off
+pub const HEADLESS_VAR: &str = "AOC_HEADLESS";
+
+/// Clear the terminal and home the cursor
+const CLEAR: &str = "\x1B[2J\x1B[H";
+
+/// Shows successive frames in the terminal and optionally records them as an asciicast v2 file.
+/// Frames are whole screens, such as a `Grid::render`.
+#[derive(Debug)]
+pub struct Recorder {
+    live: bool,
+    path: Option<String>,
+    frame_time: Option<Duration>,
+    started: Instant,
+    last_shown: Option<Instant>,
+    /// Seconds since the start, and the frame shown then
+    frames: Vec<(f64, String)>,
+    width: usize,
+    height: usize,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder {
+            live: true,
+            path: None,
+            frame_time: None,
+            started: Instant::now(),
+            last_shown: None,
+            frames: Vec::new(),
+            width: 0,
+            height: 0,
+        }
+    }
+}
+
+impl Recorder {
+    /// Live playback as fast as frames arrive, recording nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configured by `AOC_CAST`, `AOC_FPS` and `AOC_HEADLESS`
+    pub fn from_env() -> Self {
+        let mut r = Recorder::new();
+        if let Ok(path) = env::var(CAST_VAR) {
+            r = r.record_to(&path);
+        }
+        if let Some(fps) = env::var(FPS_VAR).ok().and_then(|s| s.parse().ok()) {
+            r = r.fps(fps);
+        }
+        if env::var(HEADLESS_VAR).is_ok() {
+            r = r.headless();
+        }
+        r
+    }
+
+    /// Write no terminal output at all
+    pub fn headless(mut self) -> Self {
+        self.live = false;
+        self
+    }
+
+    /// Keep every frame, to be written to `path` by `finish`
+    pub fn record_to(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Hold each live frame for `1 / fps` seconds, and space the recorded frames that far apart
+    /// rather than by when they arrived. Zero leaves playback unthrottled.
+    pub fn fps(mut self, fps: u32) -> Self {
+        self.frame_time = match fps {
+            0 => None,
+            fps => Some(Duration::from_secs(1) / fps),
+        };
+        self
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.live
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.path.is_some()
+    }
+
+    pub fn frame(&mut self, frame: &str) {
+        if self.live {
+            if let (Some(last), Some(frame_time)) = (self.last_shown, self.frame_time) {
+                let elapsed = last.elapsed();
+                if elapsed < frame_time {
+                    sleep(frame_time - elapsed);
+                }
+            }
+            // carriage returns too, so frames also draw right on a terminal in raw mode
+            print!("{}{}\r\n", CLEAR, frame.replace('\n', "\r\n"));
+            let _ = io::stdout().flush();
+            self.last_shown = Some(Instant::now());
+        }
+        if self.is_recording() {
+            let time = match self.frame_time {
+                Some(frame_time) => frame_time.as_secs_f64() * self.frames.len() as f64,
+                None => self.started.elapsed().as_secs_f64(),
+            };
+            self.width = self
+                .width
+                .max(frame.lines().map(|l| l.chars().count()).max().unwrap_or(0));
+            self.height = self.height.max(frame.lines().count() + 1);
+            self.frames.push((time, frame.to_string()));
+        }
+    }
+
+    /// The recording in asciicast v2 format: a header line, then one output event per frame
+    pub fn to_asciicast(&self) -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut cast = format!(
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}}}\n",
+            self.width.max(1),
+            self.height.max(1),
+            timestamp
+        );
+        for (time, frame) in &self.frames {
+            let text = format!("{}{}\n", CLEAR, frame).replace('\n', "\r\n");
+            writeln!(cast, "[{:.6}, \"o\", \"{}\"]", time, escape(&text)).unwrap();
+        }
+        cast
+    }
+
+    /// Write the recording, if there is one to write
+    pub fn finish(self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => fs::write(path, self.to_asciicast()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Quote text for a JSON string
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("a\"b\\c\r\n\x1B"), "a\\\"b\\\\c\\r\\n\\u001b");
+        assert_eq!(escape("•◊"), "•◊");
+    }
+
+    #[test]
+    fn recording() {
+        let mut r = Recorder::new().headless().record_to("unused.cast").fps(4);
+        r.frame("ab\ncd");
+        r.frame("abc");
+        let cast = r.to_asciicast();
+        let lines = cast.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(
+            lines[0].starts_with("{\"version\": 2, \"width\": 3, \"height\": 3, \"timestamp\": ")
+        );
+        assert_eq!(
+            lines[1],
+            "[0.000000, \"o\", \"\\u001b[2J\\u001b[Hab\\r\\ncd\\r\\n\"]"
+        );
+        assert_eq!(
+            lines[2],
+            "[0.250000, \"o\", \"\\u001b[2J\\u001b[Habc\\r\\n\"]"
+        );
+    }
+
+    #[test]
+    fn not_recording() {
+        let mut r = Recorder::new().headless();
+        r.frame("ab");
+        assert!(r.frames.is_empty());
+        assert!(r.finish().is_ok());
+    }
+}
@@ -1,22 +1,23 @@
 #![allow(dead_code)]
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::error::Error;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
 use num::integer::gcd;
 
-type Point = (i64, i64);
-type Ubermap = HashMap<Point, Map>;
+use crate::grid::{Grid, Point, SparseGrid};
+
+type Ubermap = SparseGrid<Map>;
 type Map = HashSet<Point>;
 
 pub fn find_best(map: &mut Ubermap) -> (Point, usize) {
-    let nodes = map.keys().cloned().collect::<Vec<Point>>();
+    let nodes = map.points().collect::<Vec<Point>>();
     let map_extent = get_map_extents(&map);
     print_map(&map);
     let mut k = 0;
     for node in nodes {
-        for other in &map[&node].clone() {
+        for other in &map.get(node).unwrap().clone() {
             if &node == other {
                 continue;
             }
@@ -35,13 +36,13 @@ pub fn find_best(map: &mut Ubermap) -> (Point, usize) {
                 for i in 0..occ_len {
                     let mut j = i + 2;
                     while let Some(m) = occ.get(j) {
-                        map.get_mut(&occ[i]).unwrap().remove(m);
+                        map.get_mut(occ[i]).unwrap().remove(m);
                         j += 1;
                     }
 
                     let mut j: isize = i as isize - 2;
                     while j >= 0 {
-                        map.get_mut(&occ[i])
+                        map.get_mut(occ[i])
                             .unwrap()
                             .remove(occ.get(j as usize).unwrap());
                         j -= 1;
@@ -54,7 +55,7 @@ pub fn find_best(map: &mut Ubermap) -> (Point, usize) {
     }
     let best = map.iter().max_by_key(|(_, v)| v.len()).unwrap();
     println!("k is {} of {}", k, map.len());
-    (*best.0, best.1.len() - 1)
+    (best.0, best.1.len() - 1)
 }
 
 fn read_map_file(filename: &str) -> Result<Vec<String>, Box<dyn Error>> {
@@ -64,34 +65,17 @@ fn read_map_file(filename: &str) -> Result<Vec<String>, Box<dyn Error>> {
 }
 
 fn parse_map(lines: Vec<String>) -> Result<Ubermap, Box<dyn Error>> {
-    let locs = lines
-        .iter()
-        .enumerate()
-        .flat_map(|(i, l)| {
-            l.bytes()
-                .into_iter()
-                .enumerate()
-                .map(|(j, b)| {
-                    if b == b'#' {
-                        Some((j as i64, i as i64))
-                    } else {
-                        None
-                    }
-                })
-                .filter(Option::is_some)
-                .map(|p| p.unwrap())
-                .collect::<Vec<Point>>()
-        })
-        .collect::<Vec<Point>>();
-    let proto = locs.iter().cloned().collect::<HashSet<Point>>();
-    Ok(locs.into_iter().map(|p| (p, proto.clone())).collect())
+    let asteroids = SparseGrid::parse(
+        &lines.join("\n"),
+        |c| if c == '#' { Some(()) } else { None },
+    );
+    let proto = asteroids.points().collect::<HashSet<Point>>();
+    Ok(asteroids.points().map(|p| (p, proto.clone())).collect())
 }
 
 fn get_map_extents(map: &Ubermap) -> Point {
-    (
-        map.keys().map(|p| p.1).max().unwrap(),
-        map.keys().map(|p| p.0).max().unwrap(),
-    )
+    let b = map.bounds().unwrap();
+    (b.max.1, b.max.0)
 }
 
 pub fn run() -> Result<String, Box<dyn Error>> {
@@ -147,24 +131,19 @@ fn get_grid_points(mut x: i64, mut y: i64, slope: &Slope, max_x: i64, max_y: i64
 }
 
 fn print_map(map: &Ubermap) {
-    let (max_x, max_y) = get_map_extents(map);
-
-    for y in 0..=max_y {
-        for x in 0..=max_x {
-            match map.get(&(x, y)) {
-                Some(v) => print!("{:2} ", v.len() - 1),
-                None => print!(" . "),
-            }
-        }
-        println!("");
-    }
-    println!("");
+    println!(
+        "{}",
+        map.render(false, |v| match v {
+            Some(v) => format!("{:2} ", v.len() - 1),
+            None => " . ".to_string(),
+        })
+    );
 }
 
 fn find_occupied_points(grid_pts: &[Point], map: &Ubermap) -> Vec<Point> {
     let mut s: Vec<Point> = grid_pts
         .iter()
-        .filter(|p| map.contains_key(p))
+        .filter(|p| map.contains(**p))
         .cloned()
         .collect();
     s.sort_by_key(|p| p.0 + p.1);
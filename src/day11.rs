@@ -1,14 +1,13 @@
-use std::collections::HashMap;
-use std::fmt::Write;
-use std::sync::mpsc::channel;
-use std::thread::spawn;
+use std::collections::HashSet;
 
-use crate::day9::build_machine;
+use crate::day2::{read_comma_file, Error};
+use crate::device::{Device, Driver, Poll};
+use crate::grid::{Grid, Point, SparseGrid};
+use crate::image::{self, Image, Palette, Rgb};
+use crate::ocr;
 
-pub type Point = (i64, i64);
-
-#[derive(Clone, Copy, Debug)]
-enum Color {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
     Black = 0,
     White = 1,
 }
@@ -19,16 +18,19 @@ impl Default for Color {
     }
 }
 
-impl std::fmt::Display for Color {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Color::Black => " ",
-                Color::White => "#",
-            }
-        )
+impl Color {
+    fn glyph(self) -> char {
+        match self {
+            Color::Black => ' ',
+            Color::White => '#',
+        }
+    }
+
+    fn rgb(self, palette: &Palette) -> Rgb {
+        match self {
+            Color::Black => palette.color("black", image::BLACK),
+            Color::White => palette.color("white", image::WHITE),
+        }
     }
 }
 
@@ -61,763 +63,125 @@ enum Rot {
     R,
 }
 
-pub fn run() -> Result<String, ()> {
-    let mut map: HashMap<Point, Color> = HashMap::new();
+/// The painting robot's protocol: it is told the color under it, and answers with the color to
+/// paint there and which way to turn
+struct Painter;
 
-    let mut location: Point = (0, 0);
-    let mut direction = Dir::U;
+impl Device for Painter {
+    type Command = Color;
+    type Event = (Color, Rot);
+    const ARITY: usize = 2;
 
-    map.insert(location, Color::White);
+    fn encode(&self, seen: &Color) -> i64 {
+        *seen as i64
+    }
 
-    println!("hello, world");
+    fn decode(&self, output: &[i64]) -> Result<(Color, Rot), Error> {
+        let color = match output[0] {
+            0 => Color::Black,
+            1 => Color::White,
+            c => return Err(Error::BadOutput(c)),
+        };
+        let rot = match output[1] {
+            0 => Rot::L,
+            1 => Rot::R,
+            r => return Err(Error::BadOutput(r)),
+        };
+        Ok((color, rot))
+    }
+}
 
-    let mut mem = PROGMEM.to_vec();
-    mem.extend(vec![0; 1024]);
-    let mut machine = build_machine(mem);
-    let (tx, rx) = channel();
-    machine.wire_output(tx);
-    let tx = machine.wire_input();
+/// Drives a painting program around the hull, feeding it the color under the robot and applying
+/// the color and turn it answers with
+pub struct HullRobot {
+    program: Vec<i64>,
+}
 
-    let t_h = spawn(move || machine.run());
+impl HullRobot {
+    pub fn new(program: Vec<i64>) -> Self {
+        HullRobot { program }
+    }
 
-    while let Ok(_) = tx.send({
-        let e = map.entry(location).or_insert(Color::Black);
-        println!("{:?} {:?} {:?}", location, direction, *e);
-        *e as i64
-    }) {
-        if let Ok(c) = rx.recv() {
-            println!("c {}", c);
-            match c {
-                0 => *map.get_mut(&location).unwrap() = Color::Black,
-                1 => *map.get_mut(&location).unwrap() = Color::White,
-                c @ _ => panic!("bad color: {}", c),
-            };
-        } else {
-            println!("color read failed");
-            break;
-        }
-        if let Ok(r) = rx.recv() {
-            println!("r {}", r);
-            match r {
-                0 => direction = direction.turn(Rot::L),
-                1 => direction = direction.turn(Rot::R),
-                t @ _ => panic!("bad direction: {}", t),
-            };
-        } else {
-            println!("rot read failed");
-            break;
+    pub fn load(filename: &str) -> Result<Self, Error> {
+        Ok(HullRobot::new(read_comma_file(filename)?))
+    }
+
+    /// Run the program from a hull that is black apart from the starting panel. Returns how many
+    /// panels were painted at least once, and the hull as it was left.
+    pub fn paint(&self, start: Color) -> Result<(usize, SparseGrid<Color>), Error> {
+        let mut hull = SparseGrid::new();
+        let mut painted = HashSet::new();
+        let mut location: Point = (0, 0);
+        let mut direction = Dir::U;
+        hull.set(location, start);
+
+        let mut robot = Driver::new(Painter, self.program.clone());
+        loop {
+            match robot.poll()? {
+                Poll::NeedsCommand => robot.send(&hull.get(location).copied().unwrap_or_default()),
+                Poll::Event((color, rot)) => {
+                    hull.set(location, color);
+                    painted.insert(location);
+                    direction = direction.turn(rot);
+                    match direction {
+                        Dir::U => location.1 += 1,
+                        Dir::D => location.1 -= 1,
+                        Dir::R => location.0 += 1,
+                        Dir::L => location.0 -= 1,
+                    };
+                }
+                Poll::Halted => break,
+            }
         }
-        match direction {
-            Dir::U => location.1 += 1,
-            Dir::D => location.1 -= 1,
-            Dir::R => location.0 += 1,
-            Dir::L => location.0 -= 1,
-        };
+        Ok((painted.len(), hull))
     }
+}
 
-    let _end_mem = t_h.join().unwrap();
-    print_map(&map, true);
-    Ok(format!("{}", map.len()))
+/// Draw the hull with north at the top
+pub fn render(hull: &SparseGrid<Color>) -> String {
+    hull.render(true, |c| c.copied().unwrap_or_default().glyph())
 }
 
-pub fn print_map<T>(map: &HashMap<Point, T>, inverted: bool)
-where
-    T: std::fmt::Debug + std::fmt::Display + Default + Copy,
-{
-    let mut buf = String::from("\x1B[2J\x1B[H");
-    //let mut buf = String::new();
+/// The registration identifier painted on the hull
+pub fn identifier(hull: &SparseGrid<Color>) -> Result<String, ocr::Error> {
+    ocr::read(hull, true, |c| c == Some(&Color::White))
+}
 
-    let (max_y, max_x) = (
-        map.keys().map(|p| p.1).max().unwrap(),
-        map.keys().map(|p| p.0).max().unwrap(),
-    );
-    let (min_y, min_x) = (
-        map.keys().map(|p| p.1).min().unwrap(),
-        map.keys().map(|p| p.0).min().unwrap(),
-    );
+pub fn run() -> Result<String, Box<dyn std::error::Error>> {
+    let robot = HullRobot::load("input/day11.txt")?;
+    let (painted, _) = robot.paint(Color::Black)?;
+    let (_, hull) = robot.paint(Color::White)?;
+    let palette = Palette::from_env()?;
+    image::export(
+        11,
+        &Image::from_grid(&hull, true, |c| {
+            c.copied().unwrap_or_default().rgb(&palette)
+        }),
+    )?;
+    Ok(format!("{}, {}", painted, identifier(&hull)?))
+}
 
-    for y in {
-        if inverted {
-            (min_y..=max_y).rev().collect::<Vec<i64>>().into_iter()
-        } else {
-            (min_y..=max_y).collect::<Vec<i64>>().into_iter()
-        }
-    } {
-        for x in min_x..=max_x {
-            write!(
-                buf,
-                "{}",
-                map.get(&(x, y))
-                    .cloned()
-                    .or(Some(Default::default()))
-                    .unwrap()
-            )
-            .unwrap();
-        }
-        write!(buf, "\n").unwrap();
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn part1() {
+        let robot = HullRobot::load("input/day11.txt").unwrap();
+        assert_eq!(robot.paint(Color::Black).unwrap().0, 1894);
     }
-    print!("{}\n", buf);
-}
 
-const PROGMEM: &[i64] = &[
-    3,
-    8, // 0
-    1005,
-    8,
-    338, // 2
-    1106,
-    0,
-    11, // 5
-    0,
-    0,
-    0,
-    104,
-    1, // 11
-    104,
-    0, // 13
-    3,
-    8, // 15
-    102,
-    -1,
-    8,
-    10, // 17
-    1001,
-    10,
-    1,
-    10, // 21
-    4,
-    10, // 25
-    1008,
-    8,
-    1,
-    10, // 29
-    4,
-    10, // 31
-    1002,
-    8,
-    1,
-    29, // 35
-    2,
-    105,
-    19,
-    10, // 39
-    1006,
-    0,
-    52, // 43
-    1,
-    1009,
-    7,
-    10,
-    1006,
-    0,
-    6,
-    3,
-    8,
-    102,
-    -1,
-    8,
-    10,
-    101,
-    1,
-    10,
-    10,
-    4,
-    10,
-    108,
-    1,
-    8,
-    10,
-    4,
-    10,
-    1001,
-    8,
-    0,
-    64,
-    2,
-    1002,
-    19,
-    10,
-    1,
-    8,
-    13,
-    10,
-    1,
-    1108,
-    16,
-    10,
-    2,
-    1003,
-    1,
-    10,
-    3,
-    8,
-    102,
-    -1,
-    8,
-    10,
-    1001,
-    10,
-    1,
-    10,
-    4,
-    10,
-    1008,
-    8,
-    1,
-    10,
-    4,
-    10,
-    1002,
-    8,
-    1,
-    103,
-    1006,
-    0,
-    10,
-    2,
-    109,
-    16,
-    10,
-    1,
-    102,
-    11,
-    10,
-    2,
-    6,
-    13,
-    10,
-    3,
-    8,
-    102,
-    -1,
-    8,
-    10,
-    1001,
-    10,
-    1,
-    10,
-    4,
-    10,
-    1008,
-    8,
-    0,
-    10,
-    4,
-    10,
-    1002,
-    8,
-    1,
-    140,
-    2,
-    102,
-    8,
-    10,
-    2,
-    4,
-    14,
-    10,
-    1,
-    8,
-    19,
-    10,
-    1006,
-    0,
-    24,
-    3,
-    8,
-    1002,
-    8,
-    -1,
-    10,
-    101,
-    1,
-    10,
-    10,
-    4,
-    10,
-    1008,
-    8,
-    0,
-    10,
-    4,
-    10,
-    1001,
-    8,
-    0,
-    177,
-    1006,
-    0,
-    16,
-    1,
-    1007,
-    17,
-    10,
-    3,
-    8,
-    102,
-    -1,
-    8,
-    10,
-    1001,
-    10,
-    1,
-    10,
-    4,
-    10,
-    108,
-    1,
-    8,
-    10,
-    4,
-    10,
-    101,
-    0,
-    8,
-    205,
-    3,
-    8,
-    1002,
-    8,
-    -1,
-    10,
-    1001,
-    10,
-    1,
-    10,
-    4,
-    10,
-    1008,
-    8,
-    0,
-    10,
-    4,
-    10,
-    102,
-    1,
-    8,
-    228,
-    1,
-    1005,
-    1,
-    10,
-    1,
-    9,
-    1,
-    10,
-    3,
-    8,
-    102,
-    -1,
-    8,
-    10,
-    101,
-    1,
-    10,
-    10,
-    4,
-    10,
-    1008,
-    8,
-    1,
-    10,
-    4,
-    10,
-    1002,
-    8,
-    1,
-    258,
-    3,
-    8,
-    1002,
-    8,
-    -1,
-    10,
-    1001,
-    10,
-    1,
-    10,
-    4,
-    10,
-    108,
-    0,
-    8,
-    10,
-    4,
-    10,
-    102,
-    1,
-    8,
-    279,
-    3,
-    8,
-    102,
-    -1,
-    8,
-    10,
-    1001,
-    10,
-    1,
-    10,
-    4,
-    10,
-    108,
-    0,
-    8,
-    10,
-    4,
-    10,
-    102,
-    1,
-    8,
-    301,
-    1,
-    3,
-    17,
-    10,
-    2,
-    7,
-    14,
-    10,
-    2,
-    6,
-    18,
-    10,
-    1,
-    1001,
-    17,
-    10,
-    101,
-    1,
-    9,
-    9,
-    1007,
-    9,
-    1088,
-    10,
-    1005,
-    10,
-    15,
-    99,
-    109,
-    660,
-    104,
-    0,
-    104,
-    1,
-    21102,
-    1,
-    48092525312,
-    1,
-    21101,
-    355,
-    0,
-    0,
-    1106,
-    0,
-    459,
-    21102,
-    665750184716,
-    1,
-    1,
-    21102,
-    366,
-    1,
-    0,
-    1106,
-    0,
-    459,
-    3,
-    10,
-    104,
-    0,
-    104,
-    1,
-    3,
-    10,
-    104,
-    0,
-    104,
-    0,
-    3,
-    10,
-    104,
-    0,
-    104,
-    1,
-    3,
-    10,
-    104,
-    0,
-    104,
-    1,
-    3,
-    10,
-    104,
-    0,
-    104,
-    0,
-    3,
-    10,
-    104,
-    0,
-    104,
-    1,
-    21102,
-    1,
-    235324768296,
-    1,
-    21101,
-    0,
-    413,
-    0,
-    1105,
-    1,
-    459,
-    21101,
-    3263212736,
-    0,
-    1,
-    21102,
-    424,
-    1,
-    0,
-    1106,
-    0,
-    459,
-    3,
-    10,
-    104,
-    0,
-    104,
-    0,
-    3,
-    10,
-    104,
-    0,
-    104,
-    0,
-    21102,
-    1,
-    709496824676,
-    1,
-    21101,
-    447,
-    0,
-    0,
-    1105,
-    1,
-    459,
-    21102,
-    988220904204,
-    1,
-    1,
-    21102,
-    1,
-    458,
-    0,
-    1106,
-    0,
-    459,
-    99,
-    109,
-    2,
-    21201,
-    -1,
-    0,
-    1,
-    21102,
-    40,
-    1,
-    2,
-    21102,
-    490,
-    1,
-    3,
-    21102,
-    1,
-    480,
-    0,
-    1105,
-    1,
-    523,
-    109,
-    -2,
-    2106,
-    0,
-    0,
-    0,
-    1,
-    0,
-    0,
-    1,
-    109,
-    2,
-    3,
-    10,
-    204,
-    -1,
-    1001,
-    485,
-    486,
-    501,
-    4,
-    0,
-    1001,
-    485,
-    1,
-    485,
-    108,
-    4,
-    485,
-    10,
-    1006,
-    10,
-    517,
-    1101,
-    0,
-    0,
-    485,
-    109,
-    -2,
-    2105,
-    1,
-    0,
-    0,
-    109,
-    4,
-    2101,
-    0,
-    -1,
-    522,
-    1207,
-    -3,
-    0,
-    10,
-    1006,
-    10,
-    540,
-    21102,
-    0,
-    1,
-    -3,
-    22101,
-    0,
-    -3,
-    1,
-    22102,
-    1,
-    -2,
-    2,
-    21102,
-    1,
-    1,
-    3,
-    21101,
-    559,
-    0,
-    0,
-    1106,
-    0,
-    564,
-    109,
-    -4,
-    2105,
-    1,
-    0,
-    109,
-    5,
-    1207,
-    -3,
-    1,
-    10,
-    1006,
-    10,
-    587,
-    2207,
-    -4,
-    -2,
-    10,
-    1006,
-    10,
-    587,
-    22102,
-    1,
-    -4,
-    -4,
-    1105,
-    1,
-    655,
-    22101,
-    0,
-    -4,
-    1,
-    21201,
-    -3,
-    -1,
-    2,
-    21202,
-    -2,
-    2,
-    3,
-    21102,
-    606,
-    1,
-    0,
-    1105,
-    1,
-    564,
-    21202,
-    1,
-    1,
-    -4,
-    21101,
-    0,
-    1,
-    -1,
-    2207,
-    -4,
-    -2,
-    10,
-    1006,
-    10,
-    625,
-    21102,
-    0,
-    1,
-    -1,
-    22202,
-    -2,
-    -1,
-    -2,
-    2107,
-    0,
-    -3,
-    10,
-    1006,
-    10,
-    647,
-    22101,
-    0,
-    -1,
-    1,
-    21101,
-    647,
-    0,
-    0,
-    105,
-    1,
-    522,
-    21202,
-    -2,
-    -1,
-    -2,
-    22201,
-    -4,
-    -2,
-    -4,
-    109,
-    -5,
-    2106,
-    0,
-    0,
-];
+    #[test]
+    fn part2() {
+        let robot = HullRobot::load("input/day11.txt").unwrap();
+        let (_, hull) = robot.paint(Color::White).unwrap();
+        assert_eq!(identifier(&hull), Ok("JKZLZJBH".to_string()));
+    }
+
+    #[test]
+    fn bad_output() {
+        // paints with color 2
+        let robot = HullRobot::new(vec![3, 9, 104, 2, 104, 0, 99]);
+        assert_eq!(robot.paint(Color::Black).unwrap_err(), Error::BadOutput(2));
+    }
+}
@@ -1,16 +1,22 @@
-use std::collections::HashMap;
-use std::sync::mpsc::channel;
-use std::thread::spawn;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::panic::{self, PanicHookInfo};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, spawn};
+use std::time::Duration;
 
-use crate::day11::{print_map, Point};
-use crate::day2::read_comma_file;
+use crate::cast::Recorder;
+use crate::day2::cheat::{Filter, Search};
 use crate::day2::Error;
-use crate::day9::build_machine;
-
-//type Map = HashMap<Point, Tile>;
+use crate::day2::{read_comma_file, IntCodeMachine, Patch};
+use crate::device::{Device, Driver, Poll};
+use crate::grid::{DenseGrid, Grid, Point};
+use crate::image::{self, Image, Palette, Rgb};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Tile {
+pub enum Tile {
     Empty,
     Wall,
     Block,
@@ -25,76 +31,89 @@ impl Default for Tile {
     }
 }
 
-impl From<i64> for Tile {
-    fn from(v: i64) -> Self {
-        match v {
-            0 => Self::Empty,
-            1 => Self::Wall,
-            2 => Self::Block,
-            3 => Self::Paddle,
-            4 => Self::Ball,
-            _ => Self::Score(v),
-        }
+/// The arcade cabinet's protocol: the joystick position goes in, and each `x, y, tile` triple
+/// that comes out draws a tile, or sets the score when `x, y` is `-1, 0`
+struct Arcade;
+
+impl Device for Arcade {
+    type Command = HDir;
+    type Event = (Point, Tile);
+    const ARITY: usize = 3;
+
+    fn encode(&self, joystick: &HDir) -> i64 {
+        *joystick as i64
+    }
+
+    fn decode(&self, output: &[i64]) -> Result<(Point, Tile), Error> {
+        let tile = match (output[0], output[1], output[2]) {
+            (-1, 0, score) => Tile::Score(score),
+            (_, _, 0) => Tile::Empty,
+            (_, _, 1) => Tile::Wall,
+            (_, _, 2) => Tile::Block,
+            (_, _, 3) => Tile::Paddle,
+            (_, _, 4) => Tile::Ball,
+            (_, _, t) => return Err(Error::BadOutput(t)),
+        };
+        Ok(((output[0], output[1]), tile))
     }
 }
 
-impl std::fmt::Display for Tile {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::Empty => ' ',
-                Self::Wall => '+',
-                Self::Ball => '•',
-                Self::Block => '◊',
-                Self::Paddle => '–',
-                _ => panic!("cannot display score!"),
-            }
-        )
+impl Tile {
+    /// The score is never drawn on the screen, so it has no glyph of its own
+    fn glyph(self) -> char {
+        match self {
+            Self::Wall => '+',
+            Self::Ball => '•',
+            Self::Block => '◊',
+            Self::Paddle => '–',
+            Self::Empty | Self::Score(_) => ' ',
+        }
     }
+
+    fn rgb(self, palette: &Palette) -> Rgb {
+        let (name, default) = match self {
+            Self::Wall => ("wall", [96, 96, 96]),
+            Self::Ball => ("ball", [255, 255, 255]),
+            Self::Block => ("block", [64, 128, 255]),
+            Self::Paddle => ("paddle", [255, 160, 0]),
+            Self::Empty | Self::Score(_) => ("empty", image::BLACK),
+        };
+        palette.color(name, default)
+    }
+}
+
+fn print_screen(cast: &mut Recorder, screen: &DenseGrid<Tile>) {
+    cast.frame(&screen.render(false, |t| t.copied().unwrap_or_default().glyph()));
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum HDir {
+/// Joystick positions, and the ball's sideways motion
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HDir {
     L = -1,
     R = 1,
     S = 0,
 }
 
-/*
 impl HDir {
-    pub fn flip(&mut self) {
-        *self = match self {
-            Self::L => Self::R,
-            Self::R => Self::L,
-            _ => Self::S,
-        };
+    /// The way to push the joystick to get from `from` to `to`
+    fn toward(from: i64, to: i64) -> Self {
+        match (to - from).signum() {
+            -1 => HDir::L,
+            1 => HDir::R,
+            _ => HDir::S,
+        }
     }
 }
-*/
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum VDir {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VDir {
     U = -1,
     D = 1,
     S = 0,
 }
 
-/*
-impl VDir {
-    pub fn flip(&mut self) {
-        *self = match self {
-            Self::U => Self::D,
-            Self::D => Self::U,
-            _ => Self::S,
-        };
-    }
-}
-*/
-
-#[derive(Clone)]
-struct Ball {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ball {
     pub loc: Point,
     pub h: HDir,
     pub v: VDir,
@@ -132,11 +151,6 @@ impl Default for Ball {
 }
 
 impl Ball {
-    pub fn freeze(&mut self) {
-        self.h = HDir::S;
-        self.v = VDir::S;
-    }
-
     pub fn update(&mut self, l: Point) {
         if l.0 == self.loc.0 {
             self.h = HDir::S;
@@ -156,193 +170,534 @@ impl Ball {
         }
         self.loc = l;
     }
+}
 
-    /*
-    pub fn predict_landing(&self, h: i64, screen: &Map) -> Option<i64> {
-        if self.h == HDir::S || self.v == VDir::S {
-            return None;
+/// Blocks on the screen the game draws before any quarters go in
+fn count_blocks(program: Vec<i64>) -> Result<usize, Error> {
+    let mut arcade = Driver::new(Arcade, program);
+    let mut screen = DenseGrid::new();
+    while let Poll::Event((p, tile)) = arcade.poll()? {
+        if !matches!(tile, Tile::Score(_)) {
+            screen.set(p, tile);
         }
+    }
+    Ok(screen.values().filter(|t| **t == Tile::Block).count())
+}
 
-        let mut probe = self.clone();
-        let mut screen = screen.clone();
-        println!("probe is at {}", self);
-        let mut loops = 0;
-        while probe.loc.1 < h && probe.loc.1 >= 0 && probe.loc.0 >= 0 && probe.loc.0 < 50 {
-            if let Some(t) = screen.get(&(probe.loc.0 + probe.h as i64, probe.loc.1)) {
-                if t == &Tile::Wall {
-                    println!("found wall <->");
-                    probe.h.flip();
-                }
-                if t == &Tile::Block {
-                    println!("found block <>");
-                    probe.h.flip();
-                    screen
-                        .remove(&(probe.loc.0 + probe.h as i64, probe.loc.1))
-                        .unwrap();
-                }
+/// Memory edit that lets the game start without quarters
+const FREE_PLAY: &str = include_str!("../input/day13-freeplay.patch");
+
+/// Everything the arcade has shown so far
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GameState {
+    pub screen: DenseGrid<Tile>,
+    /// Where the ball is and which way it last moved
+    pub ball: Option<Ball>,
+    pub paddle: Option<Point>,
+    pub score: i64,
+    pub blocks: usize,
+    /// The game is over and takes no more input
+    pub halted: bool,
+}
+
+impl GameState {
+    fn apply(&mut self, p: Point, tile: Tile) {
+        match tile {
+            Tile::Score(s) => {
+                self.score = s;
+                debug!("score is {}", s);
+                return;
             }
-            if let Some(t) = screen.get(&(probe.loc.0, probe.loc.1 + probe.v as i64)) {
-                if t == &Tile::Wall {
-                    println!("found wall ^v");
-                    probe.v.flip();
-                }
-                if t == &Tile::Block {
-                    println!("found block ^v");
-                    probe.v.flip();
-                    screen
-                        .remove(&(probe.loc.0, probe.loc.1 + probe.v as i64))
-                        .unwrap();
+            Tile::Ball => match &mut self.ball {
+                None => {
+                    debug!("got new ball at {} {}", p.0, p.1);
+                    self.ball = Some(Ball {
+                        loc: p,
+                        ..Ball::default()
+                    });
                 }
-            }
-            if let Some(t) =
-                screen.get(&(probe.loc.0 + probe.h as i64, probe.loc.1 + probe.v as i64))
-            {
-                if t == &Tile::Wall {
-                    println!("found wall x");
-                    probe.v.flip();
-                    probe.h.flip();
+                Some(b) => {
+                    b.update(p);
+                    debug!("ball moved to {}", b);
                 }
-                if t == &Tile::Block {
-                    println!("found block x");
-                    probe.h.flip();
-                    probe.v.flip();
-                    screen
-                        .remove(&(probe.loc.0 + probe.h as i64, probe.loc.1 + probe.v as i64))
-                        .unwrap();
+            },
+            Tile::Paddle => self.paddle = Some(p),
+            _ => {}
+        }
+        self.screen.set(p, tile);
+    }
+
+    /// The screen with the score underneath
+    pub fn render(&self) -> String {
+        let screen = self
+            .screen
+            .render(false, |t| t.copied().unwrap_or_default().glyph());
+        format!("{}Score: {}", screen, self.score)
+    }
+}
+
+/// The arcade game as a library: each `step` feeds one joystick position and runs the program
+/// until it wants the next. Every input is kept, so the game can be replayed exactly, and once
+/// `record_to` is called each one also goes straight to a replay file.
+pub struct Cabinet {
+    arcade: Driver<Arcade>,
+    state: GameState,
+    inputs: Vec<HDir>,
+    replay_log: Option<File>,
+}
+
+impl Cabinet {
+    /// Boot the game, with quarters in if `free_play`, and run it up to the first joystick read
+    pub fn new(mut program: Vec<i64>, free_play: bool) -> Result<Self, Error> {
+        if free_play {
+            FREE_PLAY.parse::<Patch>()?.apply(&mut program)?;
+        }
+        let mut cabinet = Cabinet {
+            arcade: Driver::new(Arcade, program),
+            state: GameState::default(),
+            inputs: Vec::new(),
+            replay_log: None,
+        };
+        cabinet.advance()?;
+        Ok(cabinet)
+    }
+
+    pub fn load(filename: &str, free_play: bool) -> Result<Self, Error> {
+        Cabinet::new(read_comma_file(filename)?, free_play)
+    }
+
+    /// Boot the game for free and feed it a recorded run of inputs
+    pub fn replay(program: Vec<i64>, inputs: &[HDir]) -> Result<Self, Error> {
+        let mut cabinet = Cabinet::new(program, true)?;
+        for joystick in inputs {
+            cabinet.step(*joystick)?;
+        }
+        Ok(cabinet)
+    }
+
+    /// Run until the program asks for the joystick or halts
+    fn advance(&mut self) -> Result<(), Error> {
+        loop {
+            match self.arcade.poll()? {
+                Poll::Event((p, tile)) => self.state.apply(p, tile),
+                Poll::NeedsCommand => break,
+                Poll::Halted => {
+                    self.state.halted = true;
+                    break;
                 }
             }
-            probe.update((probe.loc.0 + probe.h as i64, probe.loc.1 + probe.v as i64));
-            println!("stepped probe to {}", probe);
-            if loops > 50 {
-                panic!("whut");
-            }
-            loops += 1;
         }
+        self.state.blocks = self
+            .state
+            .screen
+            .values()
+            .filter(|t| **t == Tile::Block)
+            .count();
+        Ok(())
+    }
 
-        if probe.loc.1 == h {
-            Some(probe.loc.0)
-        } else {
-            None
+    /// Hold the joystick at `joystick` for one tick of the game
+    pub fn step(&mut self, joystick: HDir) -> Result<&GameState, Error> {
+        if self.state.halted {
+            return Err(Error::NotRunning);
+        }
+        self.arcade.send(&joystick);
+        self.inputs.push(joystick);
+        if let Some(log) = self.replay_log.as_mut() {
+            let sep = if self.inputs.len() > 1 { "," } else { "" };
+            write!(log, "{}{}", sep, joystick as i64)?;
         }
+        self.advance()?;
+        Ok(&self.state)
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Every joystick position fed so far
+    pub fn inputs(&self) -> &[HDir] {
+        &self.inputs
+    }
+
+    /// The machine running the game, for inspecting its memory
+    pub fn machine(&self) -> &IntCodeMachine {
+        self.arcade.stepper().machine()
+    }
+
+    /// The machine running the game, for poking its memory between steps
+    pub fn machine_mut(&mut self) -> &mut IntCodeMachine {
+        self.arcade.stepper_mut().machine_mut()
+    }
+
+    /// The inputs so far as comma separated joystick values, like a program
+    fn replay_text(&self) -> String {
+        self.inputs
+            .iter()
+            .map(|j| (*j as i64).to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Write the inputs so far as a replay file
+    pub fn save_replay(&self, filename: &str) -> Result<(), Error> {
+        std::fs::write(filename, self.replay_text() + "\n")?;
+        Ok(())
+    }
+
+    /// Write the inputs so far as a replay file, then add every later input to it as it is fed,
+    /// so the file is complete however the game ends
+    pub fn record_to(&mut self, filename: &str) -> Result<(), Error> {
+        let mut log = File::create(filename)?;
+        log.write_all(self.replay_text().as_bytes())?;
+        self.replay_log = Some(log);
+        Ok(())
     }
-    */
+}
+
+/// Play `steps` ticks, moving the joystick as `steer` says, and narrow down the addresses that
+/// hold `value` of the game's state after every tick, such as the ball's x. Ticks where `value`
+/// is unknown are skipped.
+pub fn find_variable<F, S>(
+    cabinet: &mut Cabinet,
+    value: F,
+    steer: S,
+    steps: usize,
+) -> Result<Vec<usize>, Error>
+where
+    F: Fn(&GameState) -> Option<i64>,
+    S: Fn(&GameState) -> HDir,
+{
+    let mut search = Search::new(cabinet.machine().mem());
+    for _ in 0..steps {
+        let joystick = steer(cabinet.state());
+        cabinet.step(joystick)?;
+        if let Some(v) = value(cabinet.state()) {
+            search.narrow(cabinet.machine().mem(), Filter::Equals(v));
+        }
+    }
+    Ok(search.candidates().to_vec())
+}
+
+/// Steer the paddle under the ball
+fn follow(state: &GameState) -> HDir {
+    match (&state.ball, state.paddle) {
+        (Some(b), Some(p)) => HDir::toward(p.0, b.loc.0),
+        _ => HDir::S,
+    }
+}
+
+/// Read a replay file written by `Cabinet::save_replay`
+pub fn load_replay(filename: &str) -> Result<Vec<HDir>, Error> {
+    read_comma_file(filename)?
+        .into_iter()
+        .map(|v| match v {
+            -1 => Ok(HDir::L),
+            0 => Ok(HDir::S),
+            1 => Ok(HDir::R),
+            v => Err(Error::BadReplay(v)),
+        })
+        .collect()
+}
+
+/// Where the arcade modes record the joystick inputs they feed, instead of `DEFAULT_REPLAY`
+pub const REPLAY_VAR: &str = "AOC_REPLAY";
+
+/// Replay file the arcade modes record to when `AOC_REPLAY` is not set
+pub const DEFAULT_REPLAY: &str = "day13.replay";
+
+/// Record the cabinet's inputs to the path in `AOC_REPLAY`, or to `DEFAULT_REPLAY`
+fn record_replay(cabinet: &mut Cabinet) -> Result<(), Error> {
+    let path = std::env::var(REPLAY_VAR).unwrap_or_else(|_| DEFAULT_REPLAY.to_string());
+    cabinet.record_to(&path)
+}
+
+/// Play the game until it ends, keeping the paddle under the ball
+fn autopilot(cabinet: &mut Cabinet, cast: &mut Recorder) -> Result<(), Error> {
+    while !cabinet.state().halted {
+        print_screen(cast, &cabinet.state().screen);
+        let joystick = follow(cabinet.state());
+        cabinet.step(joystick)?;
+    }
+
+    let state = cabinet.state();
+    if state.blocks > 0 {
+        warn!("game over with {} blocks left", state.blocks);
+    }
+    print_screen(cast, &state.screen);
+    Ok(())
+}
+
+/// Show a replay file being played back on the cabinet with the free play patch applied, as
+/// replays are recorded. Returns the final score.
+pub fn watch(replay: &str) -> Result<i64, Error> {
+    let inputs = load_replay(replay)?;
+    let mut cabinet = Cabinet::load("input/day13.txt", true)?;
+    let mut cast = Recorder::from_env();
+    for joystick in inputs {
+        cast.frame(&cabinet.state().render());
+        cabinet.step(joystick)?;
+    }
+    cast.frame(&cabinet.state().render());
+    cast.finish()?;
+    Ok(cabinet.state().score)
 }
 
 pub fn run() -> Result<String, Error> {
-    let mut screen: HashMap<Point, Tile> = HashMap::new();
+    let program = read_comma_file("input/day13.txt")?;
+    let blocks = count_blocks(program.clone())?;
 
-    let mut data = read_comma_file("input/day13.txt")?;
-    data[0] = 2;
-    data.extend(vec![0; 4096]);
+    let mut cast = Recorder::from_env();
+    let mut cabinet = Cabinet::new(program, true)?;
+    record_replay(&mut cabinet)?;
+    autopilot(&mut cabinet, &mut cast)?;
+    cast.finish()?;
+    let state = cabinet.state();
+    let palette = Palette::from_env()?;
+    image::export(
+        13,
+        &Image::from_grid(&state.screen, false, |t| {
+            t.copied().unwrap_or_default().rgb(&palette)
+        }),
+    )?;
+    Ok(format!("{}, {}", blocks, state.score))
+}
 
-    let mut machine = build_machine(data);
-    let (tx, rx) = channel();
-    machine.wire_output(tx);
-    let tx = machine.wire_input();
-
-    let t_h = spawn(move || machine.run());
-
-    let mut score;
-    let mut ball: Option<Ball> = None;
-    let mut paddle_loc = (21, 22);
-    // let mut last_known_dest = 21;
-
-    loop {
-        match (rx.recv(), rx.recv(), rx.recv()) {
-            (Ok(x), Ok(y), Ok(tile)) => {
-                let tile = if x == -1 && y == 0 {
-                    Tile::Score(tile)
-                } else {
-                    let tile = tile.into();
-                    screen.insert((x, y), tile);
-                    tile
-                };
-
-                match tile {
-                    Tile::Ball => match &mut ball {
-                        None => {
-                            println!("got new ball at {} {}", x, y);
-                            let mut _ball = Ball::default();
-                            _ball.update((x, y));
-                            ball = Some(_ball);
-                            print_map(&screen, false);
-                            println!("");
-                            continue;
-                        }
-                        Some(b) => {
-                            b.update((x, y));
-                            println!("ball moved to {}", b);
-                        }
-                    },
-                    Tile::Score(s) => {
-                        score = s;
-                        println!("score is {}", score);
-                        ball.as_mut().unwrap().freeze();
-                        println!("ball frozen");
-                    }
-                    Tile::Paddle => {
-                        if paddle_loc.0 == 0 {
-                            paddle_loc = (x, y);
-                        }
-                        println!("paddle moved from {} to {}", paddle_loc.0, x);
-                        print_map(&screen, false);
-                        println!("");
-                        continue;
-                    }
-                    _ => {
-                        continue;
-                    }
-                }
+/// What a keypress asks of the arcade
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Key {
+    Joystick(HDir),
+    Quit,
+}
 
-                print_map(&screen, false);
+/// The keys in one read from a raw terminal: arrow keys or `a`/`d` tilt the joystick, `q`, ESC
+/// or ctrl-C/ctrl-D quit, and anything else holds it level. A terminal sends an escape sequence
+/// in a single write, so an ESC that ends the read was pressed on its own.
+fn keys(chunk: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut bytes = chunk.iter();
+    while let Some(b) = bytes.next() {
+        keys.push(match b {
+            // CSI: ESC [ then a final byte naming the arrow
+            0x1b => match (bytes.next(), bytes.next()) {
+                (None, _) => Key::Quit,
+                (Some(b'['), Some(b'D')) => Key::Joystick(HDir::L),
+                (Some(b'['), Some(b'C')) => Key::Joystick(HDir::R),
+                _ => Key::Joystick(HDir::S),
+            },
+            b'a' | b'h' => Key::Joystick(HDir::L),
+            b'd' | b'l' => Key::Joystick(HDir::R),
+            b'q' | 3 | 4 => Key::Quit,
+            _ => Key::Joystick(HDir::S),
+        });
+    }
+    keys
+}
 
-                /*
-                if let Some(dest) = ball
-                    .clone()
-                    .unwrap()
-                    .predict_landing(paddle_loc.1 - 1, &screen)
-                {
-                    last_known_dest = dest;
-                }
-                if last_known_dest == paddle_loc.0 {
-                    // stay
-                    println!("{} STAY for {}", &ball.clone().unwrap(), last_known_dest);
-                    if let Err(_) = tx.send(0) {
-                        error!("could not send STAY");
-                    }
-                } else if last_known_dest > paddle_loc.0 {
-                    // right
-                    println!("{} RIGHT for {}", &ball.clone().unwrap(), last_known_dest);
-                    paddle_loc.0 += 1;
-                    if let Err(_) = tx.send(1) {
-                        error!("could not send RIGHT");
-                    }
-                } else {
-                    // left
-                    println!("{} LEFT for {}", &ball.clone().unwrap(), last_known_dest);
-                    paddle_loc.0 -= 1;
-                    if let Err(_) = tx.send(-1) {
-                        error!("could not send LEFT");
-                    }
-                }
-                */
-                if let Err(_) = tx.send(0) {
-                    error!("bad dog");
+type PanicHook = dyn Fn(&PanicHookInfo) + Send + Sync;
+
+/// Puts the terminal in raw mode for as long as it lives. A panic restores the terminal before
+/// its message is printed.
+struct RawMode {
+    saved: String,
+    hook: Arc<PanicHook>,
+}
+
+fn restore(saved: &str) {
+    let _ = Command::new("stty")
+        .arg(saved)
+        .stdin(Stdio::inherit())
+        .status();
+}
+
+impl RawMode {
+    fn enter() -> io::Result<Self> {
+        let saved = Command::new("stty")
+            .arg("-g")
+            .stdin(Stdio::inherit())
+            .output()?;
+        if !saved.status.success() {
+            return Err(io::Error::other("stdin is not a terminal"));
+        }
+        let saved = String::from_utf8_lossy(&saved.stdout).trim().to_string();
+
+        let hook: Arc<PanicHook> = Arc::from(panic::take_hook());
+        let (panic_saved, panic_hook) = (saved.clone(), hook.clone());
+        panic::set_hook(Box::new(move |info| {
+            restore(&panic_saved);
+            panic_hook(info);
+        }));
+        // from here on, dropping the guard on an error puts the terminal back
+        let raw = RawMode { saved, hook };
+        Command::new("stty")
+            .args(["raw", "-echo"])
+            .stdin(Stdio::inherit())
+            .status()?;
+        Ok(raw)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        restore(&self.saved);
+        // the hook cannot be swapped while panicking, and the chained one still works then
+        if !thread::panicking() {
+            let hook = self.hook.clone();
+            panic::set_hook(Box::new(move |info| hook(info)));
+        }
+    }
+}
+
+/// Keys typed at the terminal, read on their own thread so the game can tick without them
+fn keyboard() -> Receiver<Key> {
+    let (tx, rx) = channel();
+    spawn(move || {
+        let mut stdin = io::stdin().lock();
+        let mut buf = [0; 64];
+        while let Ok(n @ 1..) = stdin.read(&mut buf) {
+            for key in keys(&buf[..n]) {
+                if tx.send(key).is_err() {
+                    return;
                 }
-                println!("");
-            }
-            _ => {
-                println!("done");
-                break;
             }
+        }
+    });
+    rx
+}
+
+/// Play the game at the terminal. The machine only advances when it asks for the joystick: with
+/// a `tick` it waits that long for a key before holding the joystick level, and without one it
+/// waits for every move. Returns the score when the game ends or the player quits.
+pub fn play(tick: Option<Duration>) -> Result<i64, Error> {
+    let mut cabinet = Cabinet::load("input/day13.txt", true)?;
+    record_replay(&mut cabinet)?;
+    let mut cast = Recorder::from_env();
+    let _raw = RawMode::enter()?;
+    let keys = keyboard();
+
+    while !cabinet.state().halted {
+        cast.frame(&cabinet.state().render());
+        let key = match tick {
+            Some(tick) => match keys.recv_timeout(tick) {
+                Ok(key) => key,
+                Err(RecvTimeoutError::Timeout) => Key::Joystick(HDir::S),
+                Err(RecvTimeoutError::Disconnected) => Key::Quit,
+            },
+            None => keys.recv().unwrap_or(Key::Quit),
         };
+        match key {
+            Key::Joystick(joystick) => cabinet.step(joystick)?,
+            Key::Quit => break,
+        };
+    }
+    if cabinet.state().halted {
+        cast.frame(&format!("{}\nGame over", cabinet.state().render()));
+    }
+    cast.finish()?;
+    Ok(cabinet.state().score)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day2::cheat::Freezer;
+
+    #[test]
+    fn answers() {
+        let program = read_comma_file("input/day13.txt").unwrap();
+        assert_eq!(count_blocks(program.clone()), Ok(312));
+        let mut cabinet = Cabinet::new(program.clone(), true).unwrap();
+        autopilot(&mut cabinet, &mut Recorder::new().headless()).unwrap();
+        assert_eq!(cabinet.state().score, 15909);
+        assert_eq!(cabinet.state().blocks, 0);
+        assert_eq!(cabinet.step(HDir::S), Err(Error::NotRunning));
+
+        let replay = Cabinet::replay(program, cabinet.inputs()).unwrap();
+        assert_eq!(replay.state(), cabinet.state());
     }
 
-    let _ = t_h.join().unwrap();
+    #[test]
+    fn wide_paddle() {
+        let ball_x = |s: &GameState| s.ball.as_ref().map(|b| b.loc.0);
+        let mut cabinet = Cabinet::load("input/day13.txt", true).unwrap();
+        let ball = find_variable(&mut cabinet, ball_x, follow, 200).unwrap();
+        assert!(ball.contains(&388));
+        let mut cabinet = Cabinet::load("input/day13.txt", true).unwrap();
+        let paddle = find_variable(&mut cabinet, |s| s.paddle.map(|p| p.0), follow, 200).unwrap();
+        assert_eq!(paddle, vec![392]);
 
-    print_map(&screen, false);
-    Ok(format!(
-        "{}",
-        screen.values().filter(|v| **v == Tile::Block).count()
-    ))
+        // the screen cell the paddle leaves when it moves
+        let mut cabinet = Cabinet::load("input/day13.txt", true).unwrap();
+        let (x, y) = cabinet.state().paddle.unwrap();
+        let mut search = Search::new(cabinet.machine().mem());
+        cabinet.step(HDir::R).unwrap();
+        let left = search.narrow_by(cabinet.machine().mem(), |before, now| {
+            // paddle tile, then empty
+            before == 3 && now == 0
+        });
+        assert_eq!(left.len(), 1);
+
+        // paddle tiles all the way across, put back every tick
+        let row = left[0] - x as usize;
+        let width = cabinet.state().screen.bounds().unwrap().width();
+        let mut freezer = Freezer::new();
+        for addr in row + 1..row + width - 1 {
+            freezer.freeze(addr, 3);
+        }
+        while !cabinet.state().halted {
+            freezer.apply(cabinet.machine_mut()).unwrap();
+            cabinet.step(HDir::S).unwrap();
+            assert_ne!(cabinet.state().ball.as_ref().map(|b| b.loc.1), Some(y + 1));
+        }
+        assert_eq!(cabinet.state().blocks, 0);
+        assert_eq!(cabinet.state().score, 15909);
+    }
+
+    #[test]
+    fn free_play() {
+        // the patch checks it is feeding quarters to a fresh cabinet
+        let mut program = read_comma_file("input/day13.txt").unwrap();
+        program[0] = 3;
+        assert!(Cabinet::new(program, true).is_err());
+    }
+
+    #[test]
+    fn replay_file() {
+        let mut cabinet = Cabinet::load("input/day13.txt", true).unwrap();
+        assert_eq!(cabinet.state().blocks, 312);
+        let paddle = cabinet.state().paddle.unwrap();
+        let state = cabinet.step(HDir::L).unwrap();
+        assert_eq!(state.paddle, Some((paddle.0 - 1, paddle.1)));
+        let recorded = std::env::temp_dir().join("aoc2019_day13_recorded.replay");
+        let recorded = recorded.to_str().unwrap();
+        cabinet.record_to(recorded).unwrap();
+        cabinet.step(HDir::R).unwrap();
+        cabinet.step(HDir::S).unwrap();
+        assert_eq!(std::fs::read_to_string(recorded).unwrap(), "-1,1,0");
+        assert_eq!(load_replay(recorded).unwrap(), cabinet.inputs());
+
+        let path = std::env::temp_dir().join("aoc2019_day13.replay");
+        let path = path.to_str().unwrap();
+        cabinet.save_replay(path).unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "-1,1,0\n");
+        assert_eq!(load_replay(path).unwrap(), cabinet.inputs());
+
+        std::fs::write(path, "-1,2\n").unwrap();
+        assert_eq!(load_replay(path), Err(Error::BadReplay(2)));
+    }
+
+    #[test]
+    fn keys() {
+        let (l, r, s) = (
+            Key::Joystick(HDir::L),
+            Key::Joystick(HDir::R),
+            Key::Joystick(HDir::S),
+        );
+        assert_eq!(
+            super::keys(b"\x1b[D\x1b[Cad x\x1b[Aq"),
+            vec![l, r, l, r, s, s, s, Key::Quit]
+        );
+        // ESC on its own
+        assert_eq!(super::keys(b"a\x1b"), vec![l, Key::Quit]);
+    }
 }
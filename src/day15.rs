@@ -1,13 +1,17 @@
-use std::collections::HashMap;
-use std::sync::mpsc::channel;
-use std::thread::spawn;
+use std::collections::HashSet;
+use std::env;
+use std::error;
+use std::fs;
+use std::thread;
 
-use crate::day11::{print_map, Point};
-use crate::day2::read_comma_file;
+use crate::cast::Recorder;
 use crate::day2::Error;
-use crate::day9::build_machine;
+use crate::device::{Device, Driver};
+use crate::grid::{Glyph, Grid, Point, SparseGrid};
+use crate::image::{self, Image, Palette, Rgb};
+use crate::search;
 
-type Map = HashMap<Point, Tile>;
+type Map = SparseGrid<Tile>;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Tile {
@@ -16,6 +20,8 @@ enum Tile {
     Empty,
     Oxygen,
     Visited,
+    /// On the shortest route to the oxygen system
+    Path,
 }
 
 impl Default for Tile {
@@ -24,19 +30,50 @@ impl Default for Tile {
     }
 }
 
-impl std::fmt::Display for Tile {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::Start => 'Ø',
-                Self::Wall => '+',
-                Self::Empty => ' ',
-                Self::Oxygen => '',
-                Self::Visited => '•',
-            }
-        )
+impl Glyph for Tile {
+    const ALL: &'static [Tile] = &[
+        Self::Start,
+        Self::Wall,
+        Self::Visited,
+        Self::Oxygen,
+        Self::Path,
+    ];
+
+    fn glyph(self) -> char {
+        match self {
+            Self::Start => 'Ø',
+            Self::Wall => '+',
+            Self::Empty => ' ',
+            Self::Oxygen => '\u{f8ff}',
+            Self::Visited => '•',
+            Self::Path => 'o',
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Wall => "wall",
+            Self::Empty => "unmapped",
+            Self::Oxygen => "oxygen system",
+            Self::Visited => "open",
+            Self::Path => "route",
+        }
+    }
+}
+
+impl Tile {
+    /// The tile's color, unless the palette sets one for its legend name
+    fn rgb(self, palette: &Palette) -> Rgb {
+        let default = match self {
+            Self::Start => [255, 64, 64],
+            Self::Wall => [96, 96, 96],
+            Self::Empty => image::BLACK,
+            Self::Oxygen => [64, 160, 255],
+            Self::Visited => [200, 200, 200],
+            Self::Path => [255, 200, 0],
+        };
+        palette.color(self.name(), default)
     }
 }
 
@@ -48,6 +85,54 @@ enum Card {
     E,
 }
 
+impl Card {
+    /// The move that undoes this one
+    fn back(self) -> Card {
+        match self {
+            Card::N => Card::S,
+            Card::S => Card::N,
+            Card::W => Card::E,
+            Card::E => Card::W,
+        }
+    }
+}
+
+/// Order the droid tries unmapped neighbors in
+const CARDS: [Card; 4] = [Card::N, Card::E, Card::S, Card::W];
+
+/// What the droid reports after a movement command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    /// Hit a wall and stayed put
+    Wall,
+    Moved,
+    /// Moved onto the oxygen system
+    Oxygen,
+}
+
+/// The repair droid's protocol: a compass direction to move in, answered by a status code
+#[derive(Clone)]
+struct Droid;
+
+impl Device for Droid {
+    type Command = Card;
+    type Event = Status;
+    const ARITY: usize = 1;
+
+    fn encode(&self, cmd: &Card) -> i64 {
+        *cmd as i64
+    }
+
+    fn decode(&self, output: &[i64]) -> Result<Status, Error> {
+        match output[0] {
+            0 => Ok(Status::Wall),
+            1 => Ok(Status::Moved),
+            2 => Ok(Status::Oxygen),
+            s => Err(Error::BadOutput(s)),
+        }
+    }
+}
+
 fn move_pt(l: Point, d: Card) -> Point {
     match d {
         Card::N => (l.0, l.1 + 1),
@@ -57,107 +142,372 @@ fn move_pt(l: Point, d: Card) -> Point {
     }
 }
 
-pub fn run() -> Result<String, Error> {
-    let mut map: Map = HashMap::new();
+fn render(map: &Map) -> String {
+    map.render(false, |t| t.copied().unwrap_or_default().glyph())
+}
 
-    let mut data = read_comma_file("input/day15.txt")?;
-    data.extend(vec![0; 4096]);
+/// Where `run` saves the explored map, when set
+pub const MAP_VAR: &str = "AOC_MAP";
 
-    let mut machine = build_machine(data);
-    let (tx, rx) = channel();
-    machine.wire_output(tx);
-    let tx = machine.wire_input();
+/// Read a map file written by `save_map`
+fn load_map(filename: &str) -> Result<Map, Box<dyn error::Error>> {
+    Ok(SparseGrid::from_text(&fs::read_to_string(filename)?)?)
+}
 
-    let _t_h = spawn(move || machine.run());
+fn save_map(map: &Map, filename: &str) -> Result<(), Error> {
+    fs::write(filename, map.to_text())?;
+    Ok(())
+}
 
+/// Walk the droid into every reachable cell, depth first, and map everything it bumps into
+fn explore(droid: &mut Driver<Droid>, cast: &mut Recorder) -> Result<Map, Error> {
+    let mut map: Map = SparseGrid::new();
     let mut loc = (0, 0);
-    let mut next_cmd = Card::N;
-    map.insert((0, 0), Tile::Start);
-
-    let mut visited_locs = vec![loc];
-
-    let mut best_path: Option<Vec<Point>> = None;
-
-    while visited_locs.len() > 0 {
-        println!("sending cmd {:?}", next_cmd);
-        tx.send(next_cmd as i64).unwrap();
-        if let Ok(status) = rx.recv() {
-            println!("got response {}", status);
-            match status {
-                0 => {
-                    map.insert(move_pt(loc, next_cmd), Tile::Wall);
-                    next_cmd = match which_next(loc, &map) {
-                        None => dir_from(loc, visited_locs.pop().unwrap()), // backtrack
-                        Some(d) => d,
-                    };
-                }
-                1 => {
-                    loc = move_pt(loc, next_cmd);
-                    map.insert(loc, Tile::Visited);
-                    next_cmd = match which_next(loc, &map) {
-                        None => dir_from(loc, visited_locs.pop().unwrap()),
-                        Some(d) => {
-                            visited_locs.push(loc);
-                            d
-                        }
+    map.set(loc, Tile::Start);
+
+    // the moves that led here, retraced once every neighbor is mapped
+    let mut trail: Vec<Card> = Vec::new();
+    loop {
+        let unmapped = CARDS
+            .iter()
+            .copied()
+            .find(|c| map.get(move_pt(loc, *c)).is_none());
+        let cmd = match (unmapped, trail.last()) {
+            (Some(c), _) => c,
+            (None, Some(c)) => c.back(),
+            (None, None) => break,
+        };
+        debug!("sending cmd {:?}", cmd);
+        let status = droid.request(&cmd)?;
+        debug!("got response {:?}", status);
+        match status {
+            Status::Wall => map.set(move_pt(loc, cmd), Tile::Wall),
+            Status::Moved | Status::Oxygen => {
+                loc = move_pt(loc, cmd);
+                if unmapped.is_some() {
+                    trail.push(cmd);
+                    let tile = match status {
+                        Status::Oxygen => Tile::Oxygen,
+                        _ => Tile::Visited,
                     };
+                    map.set(loc, tile);
+                } else {
+                    trail.pop();
                 }
-                2 => {
-                    if best_path.is_some() {
-                        if visited_locs.len() < best_path.as_ref().unwrap().len() {
-                            best_path = Some(visited_locs.clone());
-                        }
-                    } else {
-                        best_path = Some(visited_locs.clone());
-                    }
-                    loc = move_pt(loc, next_cmd);
-                    next_cmd = dir_from(loc, visited_locs.pop().unwrap()); // backtrack
-                }
-                _ => panic!("got invalid response"),
             }
         }
+        cast.frame(&render(&map));
     }
+    Ok(map)
+}
 
-    print_map(&map, false);
-    println!("{:?}", best_path);
+/// Where a probe went, what it found, the droid if it moved and the instructions that took
+type Probed = (Point, Status, Option<Driver<Droid>>, u64);
 
-    Ok(String::new())
+/// A droid forked to try one move from a mapped cell
+struct Probe {
+    to: Point,
+    cmd: Card,
+    droid: Driver<Droid>,
 }
 
-fn which_next(loc: Point, map: &Map) -> Option<Card> {
-    let mut cmd = Card::N; // try north first
-    let mut tries = 0;
-    while let Some(_) = map.get(&move_pt(loc, cmd)) {
-        cmd = match &cmd {
-            Card::N => Card::E,
-            Card::E => Card::S,
-            Card::S => Card::W,
-            Card::W => Card::N,
+impl Probe {
+    /// Make the move. The droid is kept only if it got somewhere worth exploring from.
+    fn send(mut self) -> Result<Probed, Error> {
+        let status = self.droid.request(&self.cmd)?;
+        let steps = self.droid.stepper().steps();
+        let droid = match status {
+            Status::Wall => None,
+            _ => Some(self.droid),
         };
-        tries += 1;
-        if tries == 4 {
-            break;
+        Ok((self.to, status, droid, steps))
+    }
+}
+
+/// Map the same area as `explore` without ever walking back: every open cell keeps a fork of the
+/// droid that stands on it, and the cells one step further out are tried breadth first from
+/// those forks, spread over `threads` threads. Returns the map and the instructions executed.
+fn explore_forked(
+    droid: &Driver<Droid>,
+    threads: usize,
+    cast: &mut Recorder,
+) -> Result<(Map, u64), Error> {
+    let mut map: Map = SparseGrid::new();
+    map.set((0, 0), Tile::Start);
+    let mut frontier = vec![((0, 0), droid.fork())];
+    let mut steps = 0;
+
+    while !frontier.is_empty() {
+        // one probe per unmapped cell, even where two open cells border it
+        let mut claimed = HashSet::new();
+        let mut probes = Vec::new();
+        for (loc, droid) in &frontier {
+            for cmd in CARDS.iter().copied() {
+                let to = move_pt(*loc, cmd);
+                if map.get(to).is_none() && claimed.insert(to) {
+                    probes.push(Probe {
+                        to,
+                        cmd,
+                        droid: droid.fork(),
+                    });
+                }
+            }
+        }
+
+        let chunk = probes.len().div_ceil(threads.max(1));
+        let mut chunks = Vec::new();
+        while probes.len() > chunk {
+            let rest = probes.split_off(chunk);
+            chunks.push(probes);
+            probes = rest;
+        }
+        chunks.push(probes);
+        let results = thread::scope(|s| {
+            let workers = chunks
+                .into_iter()
+                .map(|c| s.spawn(|| c.into_iter().map(Probe::send).collect::<Vec<_>>()))
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().expect("probe thread panicked"))
+                .collect::<Vec<_>>()
+        });
+
+        frontier = Vec::new();
+        for result in results {
+            let (to, status, droid, probe_steps) = result?;
+            steps += probe_steps;
+            map.set(
+                to,
+                match status {
+                    Status::Wall => Tile::Wall,
+                    Status::Moved => Tile::Visited,
+                    Status::Oxygen => Tile::Oxygen,
+                },
+            );
+            if let Some(droid) = droid {
+                frontier.push((to, droid));
+            }
         }
+        cast.frame(&render(&map));
     }
-    if tries == 4 {
-        None
-    } else {
-        Some(cmd)
+    Ok((map, steps))
+}
+
+fn open(t: &Tile) -> bool {
+    *t != Tile::Wall
+}
+
+/// The fewest-moves route from `from` to `to` through cells that are not walls, both ends
+/// included
+fn shortest_path(map: &Map, from: Point, to: Point) -> Option<Vec<Point>> {
+    search::bfs_to(from, &to, search::grid_neighbors(map, open))
+}
+
+/// The map with the steps between the ends of `path` marked
+fn with_path(map: &Map, path: &[Point]) -> Map {
+    let mut map = map.clone();
+    for p in path.iter().skip(1).take(path.len().saturating_sub(2)) {
+        map.set(*p, Tile::Path);
     }
+    map
 }
 
-// to get FROM from TO to, move Card
-fn dir_from(from: Point, to: Point) -> Card {
-    println!("backtracking to {:?}", to);
-    if from.0 == to.0 {
-        if from.1 < to.1 {
-            Card::N
-        } else {
-            Card::S
+fn oxygen_system(map: &Map) -> Option<Point> {
+    map.iter()
+        .find(|(_, t)| **t == Tile::Oxygen)
+        .map(|(p, _)| p)
+}
+
+/// Oxygen spreading out of the oxygen system, one step into every open neighbor a minute
+struct Flood {
+    map: Map,
+    /// The tiles oxygen reaches in each minute, from the oxygen system itself at minute 0
+    fronts: Vec<Vec<Point>>,
+    minutes: usize,
+}
+
+impl Flood {
+    /// `None` if the map has no oxygen system
+    fn new(map: &Map) -> Option<Self> {
+        let sources = map
+            .iter()
+            .filter(|(_, t)| **t == Tile::Oxygen)
+            .map(|(p, _)| p)
+            .collect::<Vec<_>>();
+        if sources.is_empty() {
+            return None;
+        }
+        let reached = search::flood(sources, search::grid_neighbors(map, open));
+        let mut fronts = vec![Vec::new(); reached.farthest().unwrap_or(0) + 1];
+        for (p, minute) in reached.iter() {
+            fronts[minute].push(*p);
+        }
+        Some(Flood {
+            map: map.clone(),
+            fronts,
+            minutes: 0,
+        })
+    }
+
+    /// Let another minute pass. False, with nothing changed, once the area is full.
+    fn spread(&mut self) -> bool {
+        let front = match self.fronts.get(self.minutes + 1) {
+            Some(front) => front,
+            None => return false,
+        };
+        for p in front {
+            self.map.set(*p, Tile::Oxygen);
+        }
+        self.minutes += 1;
+        true
+    }
+
+    /// Spread until the area is full, showing every minute. Returns the minutes it took.
+    fn fill(&mut self, cast: &mut Recorder) -> usize {
+        cast.frame(&render(&self.map));
+        while self.spread() {
+            cast.frame(&render(&self.map));
         }
-    } else if from.0 < to.0 {
-        Card::E
-    } else {
-        Card::W
+        self.minutes
+    }
+
+    fn map(&self) -> &Map {
+        &self.map
+    }
+}
+
+/// The moves to the oxygen system and the minutes until oxygen fills the area, or `None` if the
+/// start cannot reach an oxygen system. Shows the route, then the flood.
+fn solve(map: &Map, cast: &mut Recorder) -> Result<Option<(usize, usize)>, Error> {
+    let path = match oxygen_system(map).and_then(|o| shortest_path(map, (0, 0), o)) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let routed = with_path(map, &path);
+    cast.frame(&render(&routed));
+    let palette = Palette::from_env()?;
+    image::export(
+        15,
+        &Image::from_grid(&routed, false, |t| {
+            t.copied().unwrap_or_default().rgb(&palette)
+        }),
+    )?;
+
+    let mut flood = Flood::new(map).unwrap();
+    let minutes = flood.fill(cast);
+    debug!("filled in {} minutes:\n{}", minutes, render(flood.map()));
+    Ok(Some((path.len() - 1, minutes)))
+}
+
+fn answer(solved: Option<(usize, usize)>) -> String {
+    match solved {
+        Some((moves, minutes)) => format!("{}, {}", moves, minutes),
+        None => "no route to the oxygen system".to_string(),
+    }
+}
+
+pub fn run() -> Result<String, Error> {
+    let droid = Driver::load(Droid, "input/day15.txt")?;
+    let mut cast = Recorder::from_env();
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let (map, steps) = explore_forked(&droid, threads, &mut cast)?;
+    info!("mapped in {} instructions", steps);
+    if let Ok(path) = env::var(MAP_VAR) {
+        save_map(&map, &path)?;
+    }
+
+    let solved = solve(&map, &mut cast)?;
+    cast.finish()?;
+    Ok(answer(solved))
+}
+
+/// Map the area the slow way, walking the one droid there and back, and show it going.
+/// Returns the instructions that took.
+pub fn walk() -> Result<u64, Error> {
+    let mut droid = Driver::load(Droid, "input/day15.txt")?;
+    let mut cast = Recorder::from_env();
+    explore(&mut droid, &mut cast)?;
+    cast.finish()?;
+    Ok(droid.stepper().steps())
+}
+
+/// Solve a saved map instead of exploring the real one
+pub fn run_map(filename: &str) -> Result<String, Box<dyn error::Error>> {
+    let map = load_map(filename)?;
+    let mut cast = Recorder::from_env();
+    let solved = solve(&map, &mut cast)?;
+    cast.finish()?;
+    Ok(answer(solved))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::neighbors4;
+
+    #[test]
+    fn answers() {
+        // saved with AOC_MAP; `forked` checks it still matches the droid's view
+        let map = load_map("input/day15-map.txt").unwrap();
+        let oxygen = oxygen_system(&map).unwrap();
+        let path = shortest_path(&map, (0, 0), oxygen).unwrap();
+        assert_eq!(path.len() - 1, 366);
+        assert!(path.windows(2).all(|w| neighbors4(w[0]).any(|n| n == w[1])));
+
+        let mut flood = Flood::new(&map).unwrap();
+        assert_eq!(flood.fill(&mut Recorder::new().headless()), 384);
+        assert!(flood
+            .map()
+            .values()
+            .all(|t| *t == Tile::Wall || *t == Tile::Oxygen));
+    }
+
+    #[test]
+    fn flood() {
+        // the example from the puzzle
+        let map = load_map("input/day15-example.txt").unwrap();
+        assert_eq!(run_map("input/day15-example.txt").unwrap(), "3, 4");
+        let mut flood = Flood::new(&map).unwrap();
+        let mut cast = Recorder::new().headless().record_to("unused.cast");
+        assert_eq!(flood.fill(&mut cast), 4);
+        assert_eq!(cast.to_asciicast().lines().count(), 1 + 5);
+        assert!(!flood.spread());
+        assert_eq!(flood.minutes, 4);
+    }
+
+    #[test]
+    fn forked() {
+        let mut droid = Driver::load(Droid, "input/day15.txt").unwrap();
+        let mut cast = Recorder::new().headless();
+        let (forked, steps) = explore_forked(&droid, 4, &mut cast).unwrap();
+        let (single, single_steps) = explore_forked(&droid, 1, &mut cast).unwrap();
+        assert_eq!(forked, single);
+        assert_eq!(steps, single_steps);
+
+        let walked = explore(&mut droid, &mut cast).unwrap();
+        assert_eq!(forked, walked);
+        assert_eq!(forked, load_map("input/day15-map.txt").unwrap());
+        assert_eq!(
+            SparseGrid::from_text(&forked.to_text()).as_ref(),
+            Ok(&forked)
+        );
+        // every cell is entered once rather than entered and walked back out of
+        assert_eq!(steps, 54660);
+        assert_eq!(droid.stepper().steps(), 83311);
+    }
+
+    #[test]
+    fn unreachable() {
+        let mut map = load_map("input/day15-walled.txt").unwrap();
+        assert_eq!(shortest_path(&map, (0, 0), (2, 0)), None);
+        assert_eq!(
+            run_map("input/day15-walled.txt").unwrap(),
+            "no route to the oxygen system"
+        );
+        map.set((1, 0), Tile::Visited);
+        let path = shortest_path(&map, (0, 0), (2, 0)).unwrap();
+        assert_eq!(path, vec![(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(with_path(&map, &path).get((1, 0)), Some(&Tile::Path));
     }
 }
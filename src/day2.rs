@@ -2,12 +2,53 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::sync::mpsc::*;
+use std::sync::Arc;
 
+pub use host::HostArgs;
 use op::add::Add;
 use op::mul::Mul;
 use op::term::Term;
 use op::OpCode;
 use param::ParamReg;
+pub use patch::{read_patched, Patch};
+pub use snapshot::build_profile;
+pub use stepper::{Stepper, Yield};
+pub use threaded::{Threaded, ThreadedMachine};
+pub use trace::{Entry, ExecLog};
+
+/// Finding and pinning program variables in memory
+pub mod cheat;
+
+/// Structured language that compiles to Intcode
+pub mod compiler;
+
+/// Shared test corpus for Intcode engines
+pub mod conformance;
+
+/// Randomized programs that must not panic any engine
+#[cfg(test)]
+mod fuzz;
+
+/// Opcodes backed by Rust closures
+mod host;
+
+/// Verified memory patches
+pub mod patch;
+
+/// Saving and restoring machine state
+mod snapshot;
+
+/// Single-threaded driver for wired machines
+mod stepper;
+
+/// Closure-threaded engine
+mod threaded;
+
+/// Reversible execution log
+pub(crate) mod trace;
+
+/// Ahead-of-time translation of Intcode to Rust source
+pub mod transpile;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
@@ -29,6 +70,40 @@ pub enum Error {
     OutputFailed,
     UserInputFailed,
     NotRunning,
+    /// Execution log is disabled or has no step to undo
+    NoHistory,
+    /// Saved machine state is malformed
+    BadSnapshot,
+    /// No ISA profile goes by this name
+    UnknownIsa(String),
+    /// Machine's opcodes or parameter modes differ from those of its named ISA profile
+    CustomIsa(String),
+    /// Conformance corpus is malformed
+    BadCorpus,
+    /// Opcode is already registered
+    OpcodeConflict(i64),
+    /// Host call op was built without a closure to call
+    UnboundHostCall,
+    /// Arithmetic on program values overflowed
+    Overflow,
+    /// Program output a value the host has no meaning for
+    BadOutput(i64),
+    /// Replay file holds a value that is not a joystick position
+    BadReplay(i64),
+    /// Program stopped or asked for input this many values into a group of outputs
+    TruncatedOutput(usize),
+    /// Patch file is malformed
+    BadPatch,
+    /// Program does not hold the value a patch expects at this address
+    PatchMismatch(usize),
+    /// Patch edits an address past the longest program it may produce
+    PatchOutOfRange(usize),
+    /// Machines were already started
+    AlreadyRunning,
+    /// A machine's thread panicked
+    ThreadPanicked,
+    /// There were no phase settings to try
+    NoPhases,
 }
 
 impl<T> From<std::sync::mpsc::SendError<T>> for Error {
@@ -61,16 +136,17 @@ impl std::fmt::Display for Error {
     }
 }
 
+impl std::error::Error for Error {}
+
 pub fn read_comma_file(filename: &str) -> Result<Vec<i64>, Error> {
     BufReader::new(File::open(filename)?)
         .split(b',')
         .map(|s| {
-            std::str::from_utf8(&s.unwrap())
-                .unwrap()
-                .trim_end()
-                .to_string()
+            let s = s?;
+            let s = std::str::from_utf8(&s)
+                .map_err(|_| Error::IoError(std::io::ErrorKind::InvalidData))?;
+            Ok(i64::from_str_radix(s.trim_end(), 10)?)
         })
-        .map(|s| i64::from_str_radix(&s, 10).map_err(|e| e.into()))
         .collect()
 }
 
@@ -83,9 +159,9 @@ pub fn run() -> Result<String, Error> {
     for n in 0..=99 {
         for v in 0..=99 {
             let mut instrs = instrs.clone();
-            instrs[1] = n;
-            instrs[2] = v;
-            let machine = IntCodeMachine::boot(instrs);
+            *instrs.get_mut(1).ok_or(Error::MemoryError(1))? = n;
+            *instrs.get_mut(2).ok_or(Error::MemoryError(2))? = v;
+            let machine = ThreadedMachine::boot(instrs);
             let end_state = machine.run()?;
             if end_state[0] == 19690720 {
                 noun = Some(n);
@@ -102,15 +178,22 @@ pub fn run() -> Result<String, Error> {
     }
 }
 
+/// Builds the op for an opcode from its parameter modes
+pub type OpCtor = Arc<dyn Fn(&ParamReg, i64) -> Result<Box<dyn OpCode>, Error> + Send + Sync>;
+
 pub struct IntCodeMachine {
     ip: isize,
-    mem: Vec<i64>,
-    op_map: HashMap<i64, fn(&ParamReg, i64) -> Result<Box<dyn OpCode>, Error>>,
+    mem: Memory,
+    op_map: HashMap<i64, OpCtor>,
     p_reg: ParamReg,
     input: Option<Receiver<i64>>,
     output: Option<Sender<i64>>,
     user_input: Option<Sender<i64>>,
     rel_base: isize,
+    exec_log: Option<ExecLog>,
+    isa: &'static str,
+    /// Opcodes or parameter modes were registered since `set_isa`
+    customized: bool,
 }
 
 impl std::fmt::Debug for IntCodeMachine {
@@ -149,22 +232,29 @@ impl IntCodeMachine {
     pub fn boot(mem: Vec<i64>) -> Self {
         let mut m = IntCodeMachine {
             ip: 0,
-            mem,
+            mem: mem.into(),
             op_map: HashMap::new(),
             p_reg: ParamReg::new(),
             input: None,
             output: None,
             user_input: None,
             rel_base: 0,
+            exec_log: None,
+            isa: "day2",
+            customized: false,
         };
         m.reg_opcode(Add::code(), Add::new);
         m.reg_opcode(Mul::code(), Mul::new);
         m.reg_opcode(Term::code(), Term::new);
         m.reg_param_mode(0, indirect::load, indirect::store);
+        m.set_isa("day2");
         m
     }
 
-    fn step(&mut self) -> Result<(), Error> {
+    pub fn step(&mut self) -> Result<(), Error> {
+        let start_ip = self.ip;
+        let start_rel_base = self.rel_base;
+
         let op = self.decode(
             *self
                 .mem
@@ -175,13 +265,18 @@ impl IntCodeMachine {
             .mem
             .get(self.ip as usize)
             .ok_or(Error::MemoryError(self.ip))?;
+        if self.exec_log.is_some() {
+            self.mem.record_writes();
+        }
         let diff = op.execute(
             self.ip,
             &mut self.mem,
             &self.input,
             &mut self.output,
             &mut self.rel_base,
-        )?;
+        );
+        let writes = self.mem.take_writes();
+        let diff = diff?;
 
         debug!("{:?}\n", &op);
         /*
@@ -213,6 +308,14 @@ impl IntCodeMachine {
         }
         // dbg!(&op, orig_ip_val, diff, self.ip);
 
+        if let Some(log) = self.exec_log.as_mut() {
+            log.push(Entry {
+                ip: start_ip,
+                rel_delta: self.rel_base - start_rel_base,
+                writes,
+            });
+        }
+
         if self.ip >= 0 {
             Ok(())
         } else {
@@ -231,16 +334,55 @@ impl IntCodeMachine {
                 Err(Error::Terminated) => {
                     //dbg!(&self);
                     info!("Terminated gracefully.");
-                    break Ok(self.mem);
-                }
-                Err(e) => {
-                    dbg!(&self);
-                    break dbg!(Err(e));
+                    break Ok(self.mem.into_vec());
                 }
+                Err(e) => break Err(e),
             }
         }
     }
 
+    pub fn ip(&self) -> isize {
+        self.ip
+    }
+
+    pub fn rel_base(&self) -> isize {
+        self.rel_base
+    }
+
+    pub fn mem(&self) -> &[i64] {
+        &self.mem
+    }
+
+    /// Name of the ISA profile the machine was built with
+    pub fn isa(&self) -> &'static str {
+        self.isa
+    }
+
+    /// Declare that the machine's opcodes and parameter modes are now exactly those of the ISA
+    /// profile called `name`. Registering any opcode or mode afterwards marks the machine as
+    /// customized again.
+    pub fn set_isa(&mut self, name: &'static str) {
+        self.isa = name;
+        self.customized = false;
+    }
+
+    /// Copy the machine's state and ISA. The copy has no I/O wired and no execution log.
+    pub fn fork(&self) -> Self {
+        IntCodeMachine {
+            ip: self.ip,
+            mem: self.mem.clone(),
+            op_map: self.op_map.clone(),
+            p_reg: self.p_reg.clone(),
+            input: None,
+            output: None,
+            user_input: None,
+            rel_base: self.rel_base,
+            exec_log: None,
+            isa: self.isa,
+            customized: self.customized,
+        }
+    }
+
     pub fn decode(&mut self, opcode: i64) -> Result<Box<dyn OpCode>, Error> {
         let (op, param) = (opcode % 100, opcode / 100);
         self.op_map.get(&op).ok_or(Error::BadOpcode(op))?(&self.p_reg, param)
@@ -251,11 +393,27 @@ impl IntCodeMachine {
         opcode: i64,
         ctor: fn(&ParamReg, i64) -> Result<Box<dyn OpCode>, Error>,
     ) {
-        self.op_map.insert(opcode, ctor);
+        self.op_map.insert(opcode, Arc::new(ctor));
+        self.customized = true;
+    }
+
+    /// Register an opcode whose ctor may capture state. Unlike `reg_opcode`, this refuses to
+    /// replace an opcode that is already registered.
+    pub fn extend_opcode<F>(&mut self, opcode: i64, ctor: F) -> Result<(), Error>
+    where
+        F: Fn(&ParamReg, i64) -> Result<Box<dyn OpCode>, Error> + Send + Sync + 'static,
+    {
+        if self.op_map.contains_key(&opcode) {
+            return Err(Error::OpcodeConflict(opcode));
+        }
+        self.op_map.insert(opcode, Arc::new(ctor));
+        self.customized = true;
+        Ok(())
     }
 
     pub fn reg_param_mode(&mut self, id: i64, load: LoadPtr, store: StorePtr) {
         self.p_reg.register_mode(id, load, store);
+        self.customized = true;
     }
 
     pub fn wire_input(&mut self) -> Sender<i64> {
@@ -278,12 +436,86 @@ impl IntCodeMachine {
     }
 }
 
+/// A machine that can be wired into a cluster and run to completion on its own thread
+pub trait Wired: Send + 'static {
+    fn wire_input(&mut self) -> Sender<i64>;
+    fn wire_output(&mut self, tx: Sender<i64>);
+    fn run(self) -> Result<Vec<i64>, Error>;
+}
+
+impl Wired for IntCodeMachine {
+    fn wire_input(&mut self) -> Sender<i64> {
+        IntCodeMachine::wire_input(self)
+    }
+
+    fn wire_output(&mut self, tx: Sender<i64>) {
+        IntCodeMachine::wire_output(self, tx)
+    }
+
+    fn run(self) -> Result<Vec<i64>, Error> {
+        IntCodeMachine::run(self)
+    }
+}
+
 pub type LoadPtr = fn(isize, &[i64], isize) -> Result<i64, Error>;
-pub type StorePtr = fn(isize, &mut [i64], i64, isize) -> Result<(), Error>;
+pub type StorePtr = fn(isize, &mut Memory, i64, isize) -> Result<(), Error>;
+
+/// Machine memory. Ops read it as a slice, but every store goes through `write`, which is where
+/// the execution log learns what a step overwrote.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Memory {
+    cells: Vec<i64>,
+    writes: Option<Vec<(usize, i64)>>,
+}
+
+impl Memory {
+    pub fn write(&mut self, addr: isize, value: i64) -> Result<(), Error> {
+        let cell = self
+            .cells
+            .get_mut(addr as usize)
+            .ok_or(Error::MemoryError(addr))?;
+        if let Some(writes) = self.writes.as_mut() {
+            writes.push((addr as usize, *cell));
+        }
+        *cell = value;
+        Ok(())
+    }
+
+    /// Start noting every write along with the value it replaced
+    fn record_writes(&mut self) {
+        self.writes = Some(Vec::new());
+    }
+
+    /// Stop noting writes, returning those made since `record_writes` in order
+    fn take_writes(&mut self) -> Vec<(usize, i64)> {
+        self.writes.take().unwrap_or_default()
+    }
+
+    pub fn into_vec(self) -> Vec<i64> {
+        self.cells
+    }
+}
+
+impl From<Vec<i64>> for Memory {
+    fn from(cells: Vec<i64>) -> Self {
+        Memory {
+            cells,
+            writes: None,
+        }
+    }
+}
+
+impl std::ops::Deref for Memory {
+    type Target = [i64];
+
+    fn deref(&self) -> &[i64] {
+        &self.cells
+    }
+}
 
 pub mod op {
     use super::param::{decompose_param, ParamReg};
-    use super::{Error, LoadPtr, StorePtr};
+    use super::{Error, LoadPtr, Memory, StorePtr};
     use mopa::Any;
 
     use std::sync::mpsc::{Receiver, Sender};
@@ -296,7 +528,7 @@ pub mod op {
         fn execute(
             &self,
             ip: isize,
-            mem: &mut [i64],
+            mem: &mut Memory,
             inp: &Option<Receiver<i64>>,
             out: &mut Option<Sender<i64>>,
             rel_base: &mut isize,
@@ -317,7 +549,7 @@ pub mod op {
         pub struct Mul(LoadPtr, LoadPtr, StorePtr);
         impl OpCode for Mul {
             fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-                let ps = decompose_param(param, Mul::width() as usize);
+                let ps = decompose_param(param, Mul::width() as usize)?;
                 Ok(Box::new(Mul(
                     reg.get(ps[0])?.load,
                     reg.get(ps[1])?.load,
@@ -328,14 +560,14 @@ pub mod op {
             fn execute(
                 &self,
                 ip: isize,
-                mem: &mut [i64],
+                mem: &mut Memory,
                 _: &Option<Receiver<i64>>,
                 _: &mut Option<Sender<i64>>,
                 rel_base: &mut isize,
             ) -> Result<isize, Error> {
                 let l = self.0(ip + 1, mem, *rel_base)?;
                 let r = self.1(ip + 2, mem, *rel_base)?;
-                let result = l * r;
+                let result = l.checked_mul(r).ok_or(Error::Overflow)?;
                 debug!("{} * {} = {}", l, r, result);
                 self.2(ip + 3, mem, result, *rel_base)?;
                 Ok(Mul::width() as isize)
@@ -361,17 +593,18 @@ pub mod op {
             use super::Mul;
             use crate::day2::indirect::*;
             use crate::day2::op::OpCode;
+            use crate::day2::Memory;
             use std::sync::mpsc::channel;
 
             #[test]
             fn mul() {
                 let (tx, rx) = channel();
-                let mut mem = vec![2, 0, 0, 4, 0];
+                let mut mem = Memory::from(vec![2, 0, 0, 4, 0]);
                 let mul = Mul(load, load, store);
                 assert!(mul
                     .execute(0, &mut mem, &Some(rx), &mut Some(tx), &mut 0)
                     .is_ok());
-                assert_eq!(mem, vec![2, 0, 0, 4, 4]);
+                assert_eq!(*mem, [2, 0, 0, 4, 4]);
             }
         }
     }
@@ -383,7 +616,7 @@ pub mod op {
 
         impl OpCode for Add {
             fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-                let ps = decompose_param(param, Add::width() as usize);
+                let ps = decompose_param(param, Add::width() as usize)?;
                 Ok(Box::new(Add(
                     reg.get(ps[0])?.load,
                     reg.get(ps[1])?.load,
@@ -394,14 +627,14 @@ pub mod op {
             fn execute(
                 &self,
                 ip: isize,
-                mem: &mut [i64],
+                mem: &mut Memory,
                 _: &Option<Receiver<i64>>,
                 _: &mut Option<Sender<i64>>,
                 rel_base: &mut isize,
             ) -> Result<isize, Error> {
                 let l = self.0(ip + 1, mem, *rel_base)?;
                 let r = self.1(ip + 2, mem, *rel_base)?;
-                let result = l + r;
+                let result = l.checked_add(r).ok_or(Error::Overflow)?;
                 debug!("{} + {} = {}", l, r, result);
                 self.2(ip + 3, mem, result, *rel_base)?;
                 Ok(Add::width() as isize)
@@ -427,17 +660,18 @@ pub mod op {
             use super::Add;
             use crate::day2::indirect::*;
             use crate::day2::op::OpCode;
+            use crate::day2::Memory;
             use std::sync::mpsc::channel;
 
             #[test]
             fn test_add() {
                 let (tx, rx) = channel();
-                let mut mem = vec![1, 0, 0, 4, 0];
+                let mut mem = Memory::from(vec![1, 0, 0, 4, 0]);
                 let add = Add(load, load, store);
                 assert!(add
                     .execute(0, &mut mem, &Some(rx), &mut Some(tx), &mut 0)
                     .is_ok());
-                assert_eq!(mem, vec![1, 0, 0, 4, 2]);
+                assert_eq!(*mem, [1, 0, 0, 4, 2]);
             }
         }
     }
@@ -455,7 +689,7 @@ pub mod op {
             fn execute(
                 &self,
                 _ip: isize,
-                _mem: &mut [i64],
+                _mem: &mut Memory,
                 _: &Option<Receiver<i64>>,
                 _: &mut Option<Sender<i64>>,
                 _: &mut isize,
@@ -469,7 +703,7 @@ pub mod op {
             }
 
             fn width() -> usize {
-                unreachable!();
+                1
             }
         }
 
@@ -482,20 +716,19 @@ pub mod op {
 }
 
 pub mod indirect {
-    use super::Error;
+    use super::{Error, Memory};
 
     pub fn load(ptr: isize, mem: &[i64], _: isize) -> Result<i64, Error> {
-        assert!(ptr >= 0);
         let iptr: isize = *mem.get(ptr as usize).ok_or(Error::MemoryError(ptr))? as isize;
         let value = *mem.get(iptr as usize).ok_or(Error::MemoryError(ptr))?;
         debug!("IND LD @{} {}", iptr, value);
         Ok(value)
     }
 
-    pub fn store(ptr: isize, mem: &mut [i64], value: i64, _: isize) -> Result<(), Error> {
-        assert!(ptr >= 0);
+    pub fn store(ptr: isize, mem: &mut Memory, value: i64, _: isize) -> Result<(), Error> {
         let iptr = *mem.get(ptr as usize).ok_or(Error::MemoryError(ptr))? as isize;
-        *mem.get_mut(iptr as usize).ok_or(Error::MemoryError(ptr))? = value;
+        mem.write(iptr, value)
+            .map_err(|_| Error::MemoryError(ptr))?;
         debug!("IND STO @{} {}", iptr, value);
         Ok(())
     }
@@ -512,13 +745,14 @@ pub mod indirect {
 
         #[test]
         fn indir_store() {
-            let mut mem = vec![12, 0];
+            let mut mem = Memory::from(vec![12, 0]);
             assert!(store(1, &mut mem, 42, 0).is_ok());
-            assert_eq!(mem, vec![42, 0]);
+            assert_eq!(*mem, [42, 0]);
         }
     }
 }
 
+#[derive(Clone)]
 pub struct LSPair {
     pub load: LoadPtr,
     pub store: StorePtr,
@@ -529,6 +763,7 @@ pub mod param {
     use super::{LSPair, LoadPtr, StorePtr};
     use std::collections::HashMap;
 
+    #[derive(Clone)]
     pub struct ParamReg {
         pub mode_map: HashMap<i64, LSPair>,
     }
@@ -549,16 +784,21 @@ pub mod param {
         }
     }
 
-    pub fn decompose_param(mut code: i64, width: usize) -> Vec<i64> {
+    /// Split the parameter modes of an opcode word into `width` digits, least significant first.
+    /// More digits than that is an error.
+    pub fn decompose_param(mut code: i64, width: usize) -> Result<Vec<i64>, Error> {
         let mut v = Vec::new();
         while code > 0 {
             v.push(code % 10);
             code /= 10;
         }
+        if v.len() > width {
+            return Err(Error::BadParamMode);
+        }
         let v_len = v.len();
-        v.into_iter()
+        Ok(v.into_iter()
             .chain(vec![0; width - v_len].into_iter())
-            .collect()
+            .collect())
     }
 
     #[cfg(test)]
@@ -567,37 +807,9 @@ pub mod param {
 
         #[test]
         fn testdecompose_param() {
-            assert_eq!(decompose_param(0, 4), vec![0, 0, 0, 0]);
-            assert_eq!(decompose_param(1100, 4), vec![0, 0, 1, 1]);
+            assert_eq!(decompose_param(0, 4), Ok(vec![0, 0, 0, 0]));
+            assert_eq!(decompose_param(1100, 4), Ok(vec![0, 0, 1, 1]));
+            assert_eq!(decompose_param(12100, 4), Err(Error::BadParamMode));
         }
     }
 }
-
-#[cfg(test)]
-mod run_test {
-    use super::*;
-
-    #[test]
-    fn test_execute() {
-        // add @0 + @0 -> @5 => 1 + 1 = 2
-        let program = vec![1, 0, 0, 5, 99, 5];
-        let r = IntCodeMachine::boot(program).run();
-        assert!(r.is_ok(), format!("r is {:?}", r));
-        assert_eq!(r.unwrap(), vec![1, 0, 0, 5, 99, 2]);
-
-        let program = vec![2, 3, 0, 3, 99];
-        let r = IntCodeMachine::boot(program).run();
-        assert!(r.is_ok());
-        assert_eq!(r.unwrap(), vec![2, 3, 0, 6, 99]);
-
-        let program = vec![2, 4, 4, 5, 99, 0];
-        let r = IntCodeMachine::boot(program).run();
-        assert!(r.is_ok());
-        assert_eq!(r.unwrap(), vec![2, 4, 4, 5, 99, 9801]);
-
-        let program = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
-        let r = IntCodeMachine::boot(program).run();
-        assert!(r.is_ok());
-        assert_eq!(r.unwrap(), vec![30, 1, 1, 4, 2, 5, 6, 0, 99]);
-    }
-}
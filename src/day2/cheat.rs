@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use super::{Error, IntCodeMachine};
+
+impl IntCodeMachine {
+    /// Overwrite one memory cell from outside the program
+    pub fn poke(&mut self, addr: usize, value: i64) -> Result<(), Error> {
+        self.mem.write(addr as isize, value)
+    }
+}
+
+/// How a candidate cell must relate to its previous snapshot to stay in the search
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Equals(i64),
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    /// Moved by exactly this much since the last snapshot
+    ChangedBy(i64),
+}
+
+impl Filter {
+    fn keeps(self, before: i64, now: i64) -> bool {
+        match self {
+            Filter::Equals(v) => now == v,
+            Filter::Changed => now != before,
+            Filter::Unchanged => now == before,
+            Filter::Increased => now > before,
+            Filter::Decreased => now < before,
+            Filter::ChangedBy(d) => now.checked_sub(before) == Some(d),
+        }
+    }
+}
+
+/// Narrows down which addresses hold a variable of a running program by comparing snapshots of
+/// its memory taken between frames
+#[derive(Clone, Debug)]
+pub struct Search {
+    candidates: Vec<usize>,
+    last: Vec<i64>,
+}
+
+impl Search {
+    /// Start with every address of `mem` as a candidate
+    pub fn new(mem: &[i64]) -> Self {
+        Search {
+            candidates: (0..mem.len()).collect(),
+            last: mem.to_vec(),
+        }
+    }
+
+    /// Keep the candidates `filter` accepts against the previous snapshot, then remember `mem`
+    /// as the new one
+    pub fn narrow(&mut self, mem: &[i64], filter: Filter) -> &[usize] {
+        self.narrow_by(mem, |before, now| filter.keeps(before, now))
+    }
+
+    /// Like `narrow`, with an arbitrary test of each cell's previous and current value
+    pub fn narrow_by<F: Fn(i64, i64) -> bool>(&mut self, mem: &[i64], keep: F) -> &[usize] {
+        let last = &self.last;
+        self.candidates
+            .retain(|a| match (last.get(*a), mem.get(*a)) {
+                (Some(before), Some(now)) => keep(*before, *now),
+                _ => false,
+            });
+        self.last = mem.to_vec();
+        &self.candidates
+    }
+
+    pub fn candidates(&self) -> &[usize] {
+        &self.candidates
+    }
+}
+
+/// Addresses pinned to fixed values, reapplied after every frame so the program cannot change
+/// them for long
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Freezer {
+    frozen: BTreeMap<usize, i64>,
+}
+
+impl Freezer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn freeze(&mut self, addr: usize, value: i64) {
+        self.frozen.insert(addr, value);
+    }
+
+    pub fn thaw(&mut self, addr: usize) {
+        self.frozen.remove(&addr);
+    }
+
+    /// Poke every frozen value back into the machine
+    pub fn apply(&self, machine: &mut IntCodeMachine) -> Result<(), Error> {
+        for (addr, value) in &self.frozen {
+            machine.poke(*addr, *value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn filters() {
+        let mut s = Search::new(&[0, 5, 5, 9, 1]);
+        assert_eq!(s.narrow(&[0, 6, 5, 8, 1], Filter::Changed), &[1, 3]);
+        assert_eq!(s.narrow(&[0, 8, 5, 8, 1], Filter::ChangedBy(2)), &[1]);
+
+        let mut s = Search::new(&[3, 3, 3]);
+        assert_eq!(s.narrow(&[4, 3, 2], Filter::Increased), &[0]);
+        let mut s = Search::new(&[3, 3, 3]);
+        assert_eq!(s.narrow(&[4, 3, 2], Filter::Decreased), &[2]);
+        assert_eq!(s.narrow(&[4, 3], Filter::Unchanged), &[] as &[usize]);
+        let mut s = Search::new(&[3, 3, 3]);
+        assert_eq!(s.narrow(&[4, 3, 2], Filter::Equals(3)), &[1]);
+    }
+
+    #[test]
+    fn freeze() {
+        let mut m = IntCodeMachine::boot(vec![1, 0, 0, 0, 99]);
+        let mut f = Freezer::new();
+        f.freeze(3, 7);
+        f.freeze(1, 4);
+        f.apply(&mut m).unwrap();
+        assert_eq!(m.mem(), &[1, 4, 0, 7, 99]);
+        f.thaw(1);
+        f.freeze(9, 0);
+        assert_eq!(f.apply(&mut m), Err(Error::MemoryError(9)));
+    }
+}
@@ -0,0 +1,834 @@
+//! A small structured language that compiles to Intcode for the `day9::build_machine` ISA.
+//!
+//! ```text
+//! fn fact(n) {
+//!     if n < 2 { return 1; }
+//!     return n * fact(n - 1);
+//! }
+//!
+//! fn main() {
+//!     let n = input();
+//!     while n > 0 {
+//!         output(fact(n));
+//!         n = n - 1;
+//!     }
+//! }
+//! ```
+//!
+//! Values are integers; `+ - *` and the comparisons `< > <= >= == !=` (yielding 0 or 1) are
+//! supported, and any nonzero value is true. Variables are local to their function.
+//! `print("...")` outputs each character of an ASCII string, which may use the escapes `\n`,
+//! `\"` and `\\`.
+//!
+//! Functions use the same calling convention as the day 11 program: the caller writes the return
+//! address to `rel[0]` and the arguments to `rel[1..]`, then jumps; the callee moves the relative
+//! base past its frame with `109 N`, and returns with `109 -N` and `2106 0 0`, leaving its result
+//! in the caller's `rel[1]`. Frames live in the stack space appended after the code.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+
+/// Zero words appended after the code for the call stack
+pub const STACK_SIZE: usize = 4096;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompileError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl std::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl From<std::io::Error> for CompileError {
+    fn from(e: std::io::Error) -> Self {
+        CompileError {
+            line: 0,
+            msg: e.to_string(),
+        }
+    }
+}
+
+fn err<T>(line: usize, msg: String) -> Result<T, CompileError> {
+    Err(CompileError { line, msg })
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Tok {
+    Num(i64),
+    Ident(String),
+    Str(String),
+    Punct(&'static str),
+}
+
+const PUNCT: &[&str] = &[
+    "<=", ">=", "==", "!=", "(", ")", "{", "}", ",", ";", "=", "+", "-", "*", "<", ">",
+];
+
+fn lex(src: &str) -> Result<Vec<(Tok, usize)>, CompileError> {
+    let mut toks = Vec::new();
+    for (n, line) in src.lines().enumerate() {
+        let line_no = n + 1;
+        let line = line.split("//").next().unwrap_or("");
+        let mut rest = line.trim_start();
+        while !rest.is_empty() {
+            let c = rest.chars().next().unwrap_or(' ');
+            let len = if c.is_ascii_digit() {
+                let len = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let v = rest[..len]
+                    .parse()
+                    .or_else(|_| err(line_no, format!("bad number {}", &rest[..len])))?;
+                toks.push((Tok::Num(v), line_no));
+                len
+            } else if c == '"' {
+                let mut text = String::new();
+                let mut chars = rest.char_indices().skip(1);
+                let len = loop {
+                    match chars.next() {
+                        Some((i, '"')) => break i + 1,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, 'n')) => text.push('\n'),
+                            Some((_, c @ ('"' | '\\'))) => text.push(c),
+                            _ => return err(line_no, "bad escape".to_string()),
+                        },
+                        Some((_, c)) if c.is_ascii() => text.push(c),
+                        Some((_, c)) => return err(line_no, format!("{:?} is not ASCII", c)),
+                        None => return err(line_no, "unterminated string".to_string()),
+                    }
+                };
+                toks.push((Tok::Str(text), line_no));
+                len
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                toks.push((Tok::Ident(rest[..len].to_string()), line_no));
+                len
+            } else {
+                match PUNCT.iter().find(|p| rest.starts_with(*p)) {
+                    Some(p) => {
+                        toks.push((Tok::Punct(p), line_no));
+                        p.len()
+                    }
+                    None => return err(line_no, format!("unexpected {:?}", c)),
+                }
+            };
+            rest = rest[len..].trim_start();
+        }
+    }
+    Ok(toks)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Num(i64),
+    Var(String, usize),
+    Input,
+    Call(String, Vec<Expr>, usize),
+    Neg(Box<Expr>),
+    Bin(BinOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug)]
+enum Stmt {
+    Let(String, Expr),
+    Assign(String, Expr, usize),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    Return(Option<Expr>),
+    Output(Expr),
+    Print(String),
+    Expr(Expr),
+}
+
+#[derive(Debug)]
+struct Func {
+    name: String,
+    params: Vec<String>,
+    body: Vec<Stmt>,
+    line: usize,
+}
+
+struct Parser {
+    toks: Vec<(Tok, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.toks
+            .get(self.pos)
+            .or_else(|| self.toks.last())
+            .map(|t| t.1)
+            .unwrap_or(0)
+    }
+
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.pos).map(|t| &t.0)
+    }
+
+    fn peek_punct(&self, p: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Punct(q)) if *q == p)
+    }
+
+    fn next(&mut self) -> Result<Tok, CompileError> {
+        let t = self.toks.get(self.pos).map(|t| t.0.clone());
+        self.pos += 1;
+        t.map_or_else(
+            || err(self.line(), "unexpected end of input".to_string()),
+            Ok,
+        )
+    }
+
+    fn expect(&mut self, p: &str) -> Result<(), CompileError> {
+        let line = self.line();
+        match self.next()? {
+            Tok::Punct(q) if q == p => Ok(()),
+            t => err(line, format!("expected {:?}, found {:?}", p, t)),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, CompileError> {
+        let line = self.line();
+        match self.next()? {
+            Tok::Ident(s) => Ok(s),
+            t => err(line, format!("expected a name, found {:?}", t)),
+        }
+    }
+
+    fn keyword(&mut self, kw: &str) -> bool {
+        if self.peek() == Some(&Tok::Ident(kw.to_string())) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn program(&mut self) -> Result<Vec<Func>, CompileError> {
+        let mut funcs = Vec::new();
+        while self.peek().is_some() {
+            let line = self.line();
+            if !self.keyword("fn") {
+                return err(line, "expected fn".to_string());
+            }
+            let name = self.ident()?;
+            self.expect("(")?;
+            let mut params = Vec::new();
+            while !self.peek_punct(")") {
+                params.push(self.ident()?);
+                if !self.peek_punct(")") {
+                    self.expect(",")?;
+                }
+            }
+            self.expect(")")?;
+            let body = self.block()?;
+            funcs.push(Func {
+                name,
+                params,
+                body,
+                line,
+            });
+        }
+        Ok(funcs)
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, CompileError> {
+        self.expect("{")?;
+        let mut stmts = Vec::new();
+        while !self.peek_punct("}") {
+            stmts.push(self.stmt()?);
+        }
+        self.expect("}")?;
+        Ok(stmts)
+    }
+
+    fn stmt(&mut self) -> Result<Stmt, CompileError> {
+        let line = self.line();
+        let stmt = if self.keyword("let") {
+            let name = self.ident()?;
+            self.expect("=")?;
+            Stmt::Let(name, self.expr()?)
+        } else if self.keyword("if") {
+            let cond = self.expr()?;
+            let then = self.block()?;
+            let otherwise = if !self.keyword("else") {
+                Vec::new()
+            } else if self.peek() == Some(&Tok::Ident("if".to_string())) {
+                vec![self.stmt()?]
+            } else {
+                self.block()?
+            };
+            return Ok(Stmt::If(cond, then, otherwise));
+        } else if self.keyword("while") {
+            let cond = self.expr()?;
+            return Ok(Stmt::While(cond, self.block()?));
+        } else if self.keyword("return") {
+            if self.peek_punct(";") {
+                Stmt::Return(None)
+            } else {
+                Stmt::Return(Some(self.expr()?))
+            }
+        } else if self.keyword("output") {
+            self.expect("(")?;
+            let e = self.expr()?;
+            self.expect(")")?;
+            Stmt::Output(e)
+        } else if self.keyword("print") {
+            self.expect("(")?;
+            let text = match self.next()? {
+                Tok::Str(text) => text,
+                t => return err(line, format!("expected a string, found {:?}", t)),
+            };
+            self.expect(")")?;
+            Stmt::Print(text)
+        } else if let (Some(Tok::Ident(name)), Some((Tok::Punct("="), _))) =
+            (self.peek().cloned(), self.toks.get(self.pos + 1))
+        {
+            self.pos += 2;
+            Stmt::Assign(name, self.expr()?, line)
+        } else {
+            Stmt::Expr(self.expr()?)
+        };
+        self.expect(";")?;
+        Ok(stmt)
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinOp)],
+        operand: fn(&mut Self) -> Result<Expr, CompileError>,
+    ) -> Result<Expr, CompileError> {
+        let mut lhs = operand(self)?;
+        while let Some((_, op)) = ops.iter().find(|(p, _)| self.peek_punct(p)) {
+            self.pos += 1;
+            lhs = Expr::Bin(*op, Box::new(lhs), Box::new(operand(self)?));
+        }
+        Ok(lhs)
+    }
+
+    fn expr(&mut self) -> Result<Expr, CompileError> {
+        self.binary(
+            &[
+                ("<=", BinOp::Le),
+                (">=", BinOp::Ge),
+                ("==", BinOp::Eq),
+                ("!=", BinOp::Ne),
+                ("<", BinOp::Lt),
+                (">", BinOp::Gt),
+            ],
+            Self::sum,
+        )
+    }
+
+    fn sum(&mut self) -> Result<Expr, CompileError> {
+        self.binary(&[("+", BinOp::Add), ("-", BinOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, CompileError> {
+        self.binary(&[("*", BinOp::Mul)], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, CompileError> {
+        if self.peek_punct("-") {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let line = self.line();
+        match self.next()? {
+            Tok::Num(v) => Ok(Expr::Num(v)),
+            Tok::Punct("(") => {
+                let e = self.expr()?;
+                self.expect(")")?;
+                Ok(e)
+            }
+            Tok::Ident(name) if self.peek_punct("(") => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                while !self.peek_punct(")") {
+                    args.push(self.expr()?);
+                    if !self.peek_punct(")") {
+                        self.expect(",")?;
+                    }
+                }
+                self.expect(")")?;
+                if name == "input" && args.is_empty() {
+                    Ok(Expr::Input)
+                } else {
+                    Ok(Expr::Call(name, args, line))
+                }
+            }
+            Tok::Ident(name) => Ok(Expr::Var(name, line)),
+            t => err(line, format!("unexpected {:?}", t)),
+        }
+    }
+}
+
+/// An instruction operand, before its mode and final value are known
+#[derive(Clone, Copy, Debug)]
+enum Opnd {
+    Imm(i64),
+    /// Slot in the current frame; slot 0 holds the return address, then come the parameters,
+    /// the locals and the temporaries
+    Slot(usize),
+    /// Raw relative offset, used for the outgoing call area just past the frame
+    Rel(i64),
+    /// Address of a label
+    Label(usize),
+    /// The frame size, negated if `true`
+    FrameSize(bool),
+}
+
+struct Codegen {
+    code: Vec<i64>,
+    labels: Vec<Option<usize>>,
+    label_fixups: Vec<(usize, usize)>,
+    funcs: HashMap<String, (usize, usize)>,
+}
+
+/// Per-function state
+struct Frame {
+    vars: HashMap<String, usize>,
+    first_temp: usize,
+    temps: usize,
+    max_temps: usize,
+    slot_fixups: Vec<(usize, usize)>,
+    size_fixups: Vec<(usize, bool)>,
+}
+
+fn collect_lets(stmts: &[Stmt], names: &mut Vec<String>) {
+    for s in stmts {
+        match s {
+            Stmt::Let(name, _) if !names.contains(name) => names.push(name.clone()),
+            Stmt::If(_, a, b) => {
+                collect_lets(a, names);
+                collect_lets(b, names);
+            }
+            Stmt::While(_, a) => collect_lets(a, names),
+            _ => {}
+        }
+    }
+}
+
+impl Codegen {
+    fn label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn place(&mut self, label: usize) {
+        self.labels[label] = Some(self.code.len());
+    }
+
+    fn emit(&mut self, frame: &mut Frame, opcode: i64, args: &[Opnd]) {
+        let modes = args.iter().rev().fold(0, |acc, a| {
+            acc * 10
+                + match a {
+                    Opnd::Slot(_) | Opnd::Rel(_) => 2,
+                    _ => 1,
+                }
+        });
+        self.code.push(modes * 100 + opcode);
+        for a in args {
+            let pos = self.code.len();
+            self.code.push(match *a {
+                Opnd::Imm(v) | Opnd::Rel(v) => v,
+                Opnd::Slot(s) => {
+                    frame.slot_fixups.push((pos, s));
+                    0
+                }
+                Opnd::Label(l) => {
+                    self.label_fixups.push((pos, l));
+                    0
+                }
+                Opnd::FrameSize(neg) => {
+                    frame.size_fixups.push((pos, neg));
+                    0
+                }
+            });
+        }
+    }
+
+    fn temp(frame: &mut Frame) -> Opnd {
+        frame.temps += 1;
+        frame.max_temps = frame.max_temps.max(frame.temps);
+        Opnd::Slot(frame.first_temp + frame.temps - 1)
+    }
+
+    fn var(frame: &Frame, name: &str, line: usize) -> Result<Opnd, CompileError> {
+        match frame.vars.get(name) {
+            Some(s) => Ok(Opnd::Slot(*s)),
+            None => err(line, format!("unknown variable {}", name)),
+        }
+    }
+
+    fn expr(&mut self, frame: &mut Frame, e: &Expr) -> Result<Opnd, CompileError> {
+        Ok(match e {
+            Expr::Num(v) => Opnd::Imm(*v),
+            Expr::Var(name, line) => Self::var(frame, name, *line)?,
+            Expr::Input => {
+                let t = Self::temp(frame);
+                self.emit(frame, 3, &[t]);
+                t
+            }
+            Expr::Neg(e) => {
+                let v = self.expr(frame, e)?;
+                let t = Self::temp(frame);
+                self.emit(frame, 2, &[v, Opnd::Imm(-1), t]);
+                t
+            }
+            Expr::Bin(op, l, r) => {
+                let (l, r) = (self.expr(frame, l)?, self.expr(frame, r)?);
+                let t = Self::temp(frame);
+                match op {
+                    BinOp::Add => self.emit(frame, 1, &[l, r, t]),
+                    BinOp::Mul => self.emit(frame, 2, &[l, r, t]),
+                    BinOp::Sub => {
+                        self.emit(frame, 2, &[r, Opnd::Imm(-1), t]);
+                        self.emit(frame, 1, &[l, t, t]);
+                    }
+                    BinOp::Lt => self.emit(frame, 7, &[l, r, t]),
+                    BinOp::Gt => self.emit(frame, 7, &[r, l, t]),
+                    BinOp::Eq => self.emit(frame, 8, &[l, r, t]),
+                    BinOp::Ne | BinOp::Le | BinOp::Ge => {
+                        match op {
+                            BinOp::Ne => self.emit(frame, 8, &[l, r, t]),
+                            BinOp::Le => self.emit(frame, 7, &[r, l, t]),
+                            _ => self.emit(frame, 7, &[l, r, t]),
+                        }
+                        self.emit(frame, 8, &[t, Opnd::Imm(0), t]);
+                    }
+                }
+                t
+            }
+            Expr::Call(name, args, line) => {
+                let (label, arity) = match self.funcs.get(name) {
+                    Some(f) => *f,
+                    None => return err(*line, format!("unknown function {}", name)),
+                };
+                if arity != args.len() {
+                    return err(
+                        *line,
+                        format!("{} takes {} arguments, not {}", name, arity, args.len()),
+                    );
+                }
+                let mut vals = Vec::new();
+                for a in args {
+                    vals.push(self.expr(frame, a)?);
+                }
+                for (i, v) in vals.into_iter().enumerate() {
+                    self.emit(frame, 1, &[v, Opnd::Imm(0), Opnd::Rel(i as i64 + 1)]);
+                }
+                let ret = self.label();
+                self.emit(frame, 1, &[Opnd::Label(ret), Opnd::Imm(0), Opnd::Rel(0)]);
+                self.emit(frame, 5, &[Opnd::Imm(1), Opnd::Label(label)]);
+                self.place(ret);
+                let t = Self::temp(frame);
+                self.emit(frame, 1, &[Opnd::Rel(1), Opnd::Imm(0), t]);
+                t
+            }
+        })
+    }
+
+    fn ret(&mut self, frame: &mut Frame, value: Opnd) {
+        self.emit(frame, 1, &[value, Opnd::Imm(0), Opnd::Slot(1)]);
+        self.emit(frame, 9, &[Opnd::FrameSize(true)]);
+        self.emit(frame, 6, &[Opnd::Imm(0), Opnd::Rel(0)]);
+    }
+
+    fn stmts(&mut self, frame: &mut Frame, stmts: &[Stmt]) -> Result<(), CompileError> {
+        for s in stmts {
+            frame.temps = 0;
+            match s {
+                Stmt::Let(name, e) => {
+                    let v = self.expr(frame, e)?;
+                    let dest = Opnd::Slot(frame.vars[name]);
+                    self.emit(frame, 1, &[v, Opnd::Imm(0), dest]);
+                }
+                Stmt::Assign(name, e, line) => {
+                    let dest = Self::var(frame, name, *line)?;
+                    let v = self.expr(frame, e)?;
+                    self.emit(frame, 1, &[v, Opnd::Imm(0), dest]);
+                }
+                Stmt::If(cond, then, otherwise) => {
+                    let c = self.expr(frame, cond)?;
+                    let (else_label, end) = (self.label(), self.label());
+                    self.emit(frame, 6, &[c, Opnd::Label(else_label)]);
+                    self.stmts(frame, then)?;
+                    self.emit(frame, 5, &[Opnd::Imm(1), Opnd::Label(end)]);
+                    self.place(else_label);
+                    self.stmts(frame, otherwise)?;
+                    self.place(end);
+                }
+                Stmt::While(cond, body) => {
+                    let (top, end) = (self.label(), self.label());
+                    self.place(top);
+                    let c = self.expr(frame, cond)?;
+                    self.emit(frame, 6, &[c, Opnd::Label(end)]);
+                    self.stmts(frame, body)?;
+                    self.emit(frame, 5, &[Opnd::Imm(1), Opnd::Label(top)]);
+                    self.place(end);
+                }
+                Stmt::Return(e) => {
+                    let v = match e {
+                        Some(e) => self.expr(frame, e)?,
+                        None => Opnd::Imm(0),
+                    };
+                    self.ret(frame, v);
+                }
+                Stmt::Output(e) => {
+                    let v = self.expr(frame, e)?;
+                    self.emit(frame, 4, &[v]);
+                }
+                Stmt::Print(text) => {
+                    for b in text.bytes() {
+                        self.emit(frame, 4, &[Opnd::Imm(b as i64)]);
+                    }
+                }
+                Stmt::Expr(e) => {
+                    self.expr(frame, e)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn func(&mut self, f: &Func) -> Result<(), CompileError> {
+        let mut names = f.params.clone();
+        collect_lets(&f.body, &mut names);
+        let mut frame = Frame {
+            vars: names
+                .iter()
+                .enumerate()
+                .map(|(i, n)| (n.clone(), i + 1))
+                .collect(),
+            first_temp: names.len() + 1,
+            temps: 0,
+            max_temps: 0,
+            slot_fixups: Vec::new(),
+            size_fixups: Vec::new(),
+        };
+
+        let label = self.funcs[&f.name].0;
+        self.place(label);
+        self.emit(&mut frame, 9, &[Opnd::FrameSize(false)]);
+        self.stmts(&mut frame, &f.body)?;
+        self.ret(&mut frame, Opnd::Imm(0));
+
+        // slot 1 carries the return value, so every frame has at least two slots
+        let size = (frame.first_temp + frame.max_temps).max(2) as i64;
+        for (pos, slot) in frame.slot_fixups {
+            self.code[pos] = slot as i64 - size;
+        }
+        for (pos, neg) in frame.size_fixups {
+            self.code[pos] = if neg { -size } else { size };
+        }
+        Ok(())
+    }
+}
+
+/// Compile `src` to a program image: the code, followed by `STACK_SIZE` words of stack
+pub fn compile(src: &str) -> Result<Vec<i64>, CompileError> {
+    let funcs = Parser {
+        toks: lex(src)?,
+        pos: 0,
+    }
+    .program()?;
+
+    let mut gen = Codegen {
+        code: Vec::new(),
+        labels: Vec::new(),
+        label_fixups: Vec::new(),
+        funcs: HashMap::new(),
+    };
+    for f in &funcs {
+        let label = gen.label();
+        if gen
+            .funcs
+            .insert(f.name.clone(), (label, f.params.len()))
+            .is_some()
+        {
+            return err(f.line, format!("{} is defined twice", f.name));
+        }
+    }
+    match gen.funcs.get("main") {
+        Some((_, 0)) => {}
+        Some(_) => return err(0, "main takes no arguments".to_string()),
+        None => return err(0, "no main function".to_string()),
+    }
+
+    // entry: point the stack past the code, call main, halt
+    let mut entry = Frame {
+        vars: HashMap::new(),
+        first_temp: 0,
+        temps: 0,
+        max_temps: 0,
+        slot_fixups: Vec::new(),
+        size_fixups: Vec::new(),
+    };
+    let (stack, halt, main) = (gen.label(), gen.label(), gen.funcs["main"].0);
+    gen.emit(&mut entry, 9, &[Opnd::Label(stack)]);
+    gen.emit(
+        &mut entry,
+        1,
+        &[Opnd::Label(halt), Opnd::Imm(0), Opnd::Rel(0)],
+    );
+    gen.emit(&mut entry, 5, &[Opnd::Imm(1), Opnd::Label(main)]);
+    gen.place(halt);
+    gen.code.push(99);
+
+    for f in &funcs {
+        gen.func(f)?;
+    }
+    gen.place(stack);
+
+    for (pos, label) in gen.label_fixups {
+        gen.code[pos] = gen.labels[label].unwrap_or(0) as i64;
+    }
+    let mut image = gen.code;
+    image.extend(vec![0; STACK_SIZE]);
+    Ok(image)
+}
+
+pub fn compile_file(filename: &str) -> Result<Vec<i64>, CompileError> {
+    let mut src = String::new();
+    File::open(filename)?.read_to_string(&mut src)?;
+    compile(&src)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day2::{Stepper, Yield};
+    use crate::day9::build_machine;
+
+    fn run(src: &str, input: &[i64]) -> Vec<i64> {
+        let image = compile(src).unwrap();
+        let mut s = Stepper::new(build_machine(image));
+        for v in input {
+            s.push_input(*v);
+        }
+        let mut output = Vec::new();
+        loop {
+            match s.resume_for(10_000_000) {
+                Ok(Yield::Output(v)) => output.push(v),
+                Ok(Yield::Halted) => break output,
+                r => panic!("{:?} after {:?}", r, output),
+            }
+        }
+    }
+
+    #[test]
+    fn countdown() {
+        let src = "
+            fn main() {
+                let n = input(); // how many
+                while n > 0 {
+                    output(n);
+                    n = n - 1;
+                }
+            }
+        ";
+        assert_eq!(run(src, &[3]), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn recursion() {
+        let src = "
+            fn fact(n) {
+                if n < 2 { return 1; }
+                return n * fact(n - 1);
+            }
+            fn fib(n) {
+                if n <= 1 { return n; }
+                return fib(n - 1) + fib(n - 2);
+            }
+            fn main() {
+                output(fact(input()));
+                output(fib(15));
+            }
+        ";
+        assert_eq!(run(src, &[10]), vec![3628800, 610]);
+    }
+
+    #[test]
+    fn operators() {
+        let src = "
+            fn pick(a, b, c) { return a * 100 + b * 10 + c; }
+            fn main() {
+                let x = 3;
+                output(x < 4); output(x > 4); output(x <= 3); output(x >= 4);
+                output(x == 3); output(x != 3); output(-x - -2); output(2 + 3 * 4);
+                output(pick(1, 2, 3));
+                if x == 4 { output(4); } else if x == 3 { output(33); } else { output(0); }
+            }
+        ";
+        assert_eq!(run(src, &[]), vec![1, 0, 1, 0, 1, 0, -1, 14, 123, 33]);
+    }
+
+    #[test]
+    fn strings() {
+        let src = r#"fn main() { print("a\"b\n"); print("\\"); }"#;
+        let out = run(src, &[]);
+        assert_eq!(
+            String::from_utf8(out.iter().map(|v| *v as u8).collect()),
+            Ok("a\"b\n\\".into())
+        );
+        assert!(compile(r#"fn main() { print("x); }"#).is_err());
+        assert!(compile(r#"fn main() { print("\t"); }"#).is_err());
+        assert!(compile("fn main() { print(1); }").is_err());
+    }
+
+    #[test]
+    fn calling_convention() {
+        let image = compile("fn main() { output(1); }").unwrap();
+        let code = &image[..image.len() - STACK_SIZE];
+        // point the stack past the code, return address at rel 0, call main, halt
+        assert_eq!(&code[..2], &[109, code.len() as i64]);
+        assert_eq!(&code[2..6], &[21101, 9, 0, 0]);
+        assert_eq!(&code[6..10], &[1105, 1, 10, 99]);
+        // main adjusts its frame, outputs, stores its result in slot 1 and returns
+        assert_eq!(&code[10..12], &[109, 2]);
+        assert_eq!(&code[12..14], &[104, 1]);
+        assert_eq!(&code[14..], &[21101, 0, 0, -1, 109, -2, 2106, 0, 0]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            compile("fn main() { output(y); }"),
+            Err(CompileError {
+                line: 1,
+                msg: "unknown variable y".to_string()
+            })
+        );
+        assert_eq!(
+            compile("fn f(a) { return a; }\nfn main() { f(1, 2); }")
+                .unwrap_err()
+                .line,
+            2
+        );
+        assert!(compile("fn f() { }").is_err());
+        assert!(compile("fn main() { let x = 1 }").is_err());
+        assert!(compile("fn main() { x = 1; }").is_err());
+    }
+}
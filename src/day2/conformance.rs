@@ -0,0 +1,208 @@
+use std::fs::File;
+use std::io::Read;
+
+use super::{build_profile, Error, Stepper, Yield};
+
+/// Instructions a case may execute before it is considered runaway
+const STEP_BUDGET: u64 = 1_000_000;
+
+/// One program from the conformance corpus and what running it must produce
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Case {
+    pub name: String,
+    pub isa: String,
+    pub program: Vec<i64>,
+    /// Zero words appended to the program before it runs
+    pub pad: usize,
+    pub input: Vec<i64>,
+    pub output: Option<Vec<i64>>,
+    /// Expected leading words of memory once the program halts
+    pub memory: Option<Vec<i64>>,
+}
+
+/// Why a case did not conform
+#[derive(Debug, PartialEq, Eq)]
+pub enum Failure {
+    Machine(Error),
+    /// The program did not halt within the step budget
+    Runaway,
+    Output {
+        expected: Vec<i64>,
+        got: Vec<i64>,
+    },
+    Memory {
+        addr: usize,
+        expected: i64,
+        got: Option<i64>,
+    },
+}
+
+impl From<Error> for Failure {
+    fn from(e: Error) -> Self {
+        Failure::Machine(e)
+    }
+}
+
+/// Something that can execute Intcode: the interpreter here, or an alternative engine
+pub trait Engine {
+    /// Run `program` on the `isa` profile with `input` queued, returning everything it output and
+    /// its final memory
+    fn execute(
+        &self,
+        isa: &str,
+        program: Vec<i64>,
+        input: &[i64],
+    ) -> Result<(Vec<i64>, Vec<i64>), Failure>;
+}
+
+/// The reference engine, `IntCodeMachine`
+pub struct Interpreter;
+
+impl Engine for Interpreter {
+    fn execute(
+        &self,
+        isa: &str,
+        program: Vec<i64>,
+        input: &[i64],
+    ) -> Result<(Vec<i64>, Vec<i64>), Failure> {
+        let mut s = Stepper::new(build_profile(isa, program)?);
+        for v in input {
+            s.push_input(*v);
+        }
+        let mut output = Vec::new();
+        loop {
+            match s.resume_for(STEP_BUDGET.saturating_sub(s.steps()))? {
+                Yield::Output(v) => output.push(v),
+                Yield::Halted => break,
+                Yield::NeedsInput => return Err(Failure::Machine(Error::NeedsInput)),
+                Yield::OutOfSteps => return Err(Failure::Runaway),
+            }
+        }
+        Ok((output, s.machine().mem().to_vec()))
+    }
+}
+
+impl Case {
+    pub fn check(&self, engine: &dyn Engine) -> Result<(), Failure> {
+        let mut program = self.program.clone();
+        program.extend(vec![0; self.pad]);
+        let (output, mem) = engine.execute(&self.isa, program, &self.input)?;
+
+        if let Some(expected) = &self.output {
+            if *expected != output {
+                return Err(Failure::Output {
+                    expected: expected.clone(),
+                    got: output,
+                });
+            }
+        }
+        if let Some(expected) = &self.memory {
+            for (addr, v) in expected.iter().enumerate() {
+                if mem.get(addr) != Some(v) {
+                    return Err(Failure::Memory {
+                        addr,
+                        expected: *v,
+                        got: mem.get(addr).cloned(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn split(values: &str) -> Result<Vec<i64>, Error> {
+    values
+        .split(',')
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| v.trim().parse::<i64>().map_err(|e| e.into()))
+        .collect()
+}
+
+/// Parse corpus text: `[name]` headers, each followed by `key value` lines. `#` starts a comment.
+pub fn parse_corpus(text: &str) -> Result<Vec<Case>, Error> {
+    let mut cases: Vec<Case> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            cases.push(Case {
+                name: name.to_string(),
+                ..Case::default()
+            });
+            continue;
+        }
+
+        let case = cases.last_mut().ok_or(Error::BadCorpus)?;
+        let mut kv = line.splitn(2, ' ');
+        let key = kv.next();
+        let value = kv.next().unwrap_or("").trim();
+        match key {
+            Some("isa") => case.isa = value.to_string(),
+            Some("program") => case.program = split(value)?,
+            Some("pad") => case.pad = value.parse()?,
+            Some("input") => case.input = split(value)?,
+            Some("output") => case.output = Some(split(value)?),
+            Some("memory") => case.memory = Some(split(value)?),
+            _ => return Err(Error::BadCorpus),
+        }
+    }
+    Ok(cases)
+}
+
+pub fn load_corpus(filename: &str) -> Result<Vec<Case>, Error> {
+    let mut text = String::new();
+    File::open(filename)?.read_to_string(&mut text)?;
+    parse_corpus(&text)
+}
+
+/// Check every case, returning the name and failure of each one that did not conform
+pub fn run_corpus(cases: &[Case], engine: &dyn Engine) -> Vec<(String, Failure)> {
+    cases
+        .iter()
+        .filter_map(|c| c.check(engine).err().map(|f| (c.name.clone(), f)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn corpus() {
+        let cases = load_corpus("input/intcode.corpus").unwrap();
+        assert!(cases.len() > 20);
+        assert_eq!(run_corpus(&cases, &Interpreter), vec![]);
+    }
+
+    #[test]
+    fn failures() {
+        let cases = parse_corpus(concat!(
+            "[wrong output]\nisa day9\nprogram 104,1,99\noutput 2\n",
+            "[starved]\nisa day9\nprogram 3,0,99\n",
+            "[runaway]\nisa day9\nprogram 1105,1,0\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            run_corpus(&cases, &Interpreter),
+            vec![
+                (
+                    "wrong output".to_string(),
+                    Failure::Output {
+                        expected: vec![2],
+                        got: vec![1]
+                    }
+                ),
+                ("starved".to_string(), Failure::Machine(Error::NeedsInput)),
+                ("runaway".to_string(), Failure::Runaway),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_corpus() {
+        assert_eq!(parse_corpus("isa day2\n"), Err(Error::BadCorpus));
+        assert_eq!(parse_corpus("[x]\nbogus 1\n"), Err(Error::BadCorpus));
+    }
+}
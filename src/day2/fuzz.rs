@@ -0,0 +1,140 @@
+use std::sync::mpsc::channel;
+
+use super::transpile::transpile;
+use super::{build_profile, read_comma_file, Stepper, ThreadedMachine, Yield};
+
+/// Programs generated per engine
+const PROGRAMS: usize = 3000;
+
+/// The interpreter's ISA profiles, which decode opcodes and parameter modes differently
+const PROFILES: [&str; 4] = ["day2", "day5", "day7", "day9"];
+
+/// Instructions each program may execute
+const STEPS: u64 = 300;
+
+/// xorshift64, so failures reproduce from the seed alone
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// An opcode word: usually valid modes, sometimes a bad digit or one digit too many
+    fn opcode(&mut self) -> (i64, usize) {
+        let (op, width) = [
+            (1, 4),
+            (2, 4),
+            (3, 2),
+            (4, 2),
+            (5, 3),
+            (6, 3),
+            (7, 4),
+            (8, 4),
+            (9, 2),
+            (99, 1),
+        ][self.below(10) as usize];
+        let modes = match self.below(8) {
+            0 => self.below(100_000) as i64,
+            _ => (0..3).fold(0, |m, _| m * 10 + self.below(3) as i64),
+        };
+        (modes * 100 + op, width)
+    }
+
+    /// Mostly small addresses and offsets, with the odd extreme mixed in
+    fn operand(&mut self) -> i64 {
+        match self.below(10) {
+            0..=7 => self.below(80) as i64 - 8,
+            8 => self.next() as i64,
+            _ => [i64::MIN, i64::MAX, -1, 0][self.below(4) as usize],
+        }
+    }
+
+    /// Whole instructions with the occasional stray word, followed by some zeroed memory
+    fn program(&mut self) -> Vec<i64> {
+        let mut program = Vec::new();
+        for _ in 0..1 + self.below(16) {
+            if self.below(10) == 0 {
+                program.push(self.operand());
+                continue;
+            }
+            let (word, width) = self.opcode();
+            program.push(word);
+            for _ in 1..width {
+                program.push(self.operand());
+            }
+        }
+        program.extend(vec![0; self.below(64) as usize]);
+        program
+    }
+}
+
+#[test]
+fn interpreter() {
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    for n in 0..PROGRAMS {
+        let isa = PROFILES[n % PROFILES.len()];
+        let mut s = Stepper::new(build_profile(isa, rng.program()).unwrap());
+        if (n / PROFILES.len()).is_multiple_of(2) {
+            s.machine_mut().enable_exec_log();
+        }
+        for _ in 0..rng.below(4) {
+            let v = rng.operand();
+            s.push_input(v);
+        }
+        // any result will do, as long as it is a result rather than a panic
+        while s.steps() < STEPS {
+            match s.resume_for(STEPS - s.steps()) {
+                Ok(Yield::Output(_)) => continue,
+                _ => break,
+            }
+        }
+        let _ = s.machine_mut().rewind_to(0);
+    }
+}
+
+#[test]
+fn threaded() {
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..PROGRAMS {
+        let program = rng.program();
+        let (i_tx, i_rx) = channel();
+        let (o_tx, _o_rx) = channel();
+        for _ in 0..rng.below(4) {
+            i_tx.send(rng.operand()).unwrap();
+        }
+        drop(i_tx);
+        let mut m = ThreadedMachine::resume(program, 0, 0, i_rx, o_tx);
+        for _ in 0..STEPS {
+            if m.step().is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn transpiler() {
+    let mut rng = XorShift(0xd1b5_4a32_d192_ed03);
+    for _ in 0..PROGRAMS {
+        transpile("f", &rng.program());
+    }
+}
+
+#[test]
+fn bad_files() {
+    let path = std::env::temp_dir().join("aoc2019_fuzz_bad_file.txt");
+    let path = path.to_str().unwrap();
+    for contents in &[&b"1,2,\xff\xfe,99"[..], b"1,2,x", b"99999999999999999999"] {
+        std::fs::write(path, contents).unwrap();
+        assert!(read_comma_file(path).is_err());
+    }
+    assert!(read_comma_file("input/does_not_exist.txt").is_err());
+}
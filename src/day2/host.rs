@@ -0,0 +1,181 @@
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
+
+use super::op::OpCode;
+use super::param::{decompose_param, ParamReg};
+use super::{Error, IntCodeMachine, LSPair, Memory};
+
+type HostFn = dyn Fn(&mut HostArgs) -> Result<(), Error> + Send + Sync;
+
+/// The parameters of a host call, resolved through their modes
+pub struct HostArgs<'a> {
+    ip: isize,
+    mem: &'a mut Memory,
+    rel_base: isize,
+    params: &'a [LSPair],
+}
+
+impl<'a> HostArgs<'a> {
+    /// Read parameter `n`, counting from 0
+    pub fn load(&self, n: usize) -> Result<i64, Error> {
+        let p = self.params.get(n).ok_or(Error::BadParamMode)?;
+        (p.load)(self.ip + 1 + n as isize, self.mem, self.rel_base)
+    }
+
+    /// Write through parameter `n`, counting from 0
+    pub fn store(&mut self, n: usize, value: i64) -> Result<(), Error> {
+        let p = self.params.get(n).ok_or(Error::BadParamMode)?;
+        (p.store)(self.ip + 1 + n as isize, self.mem, value, self.rel_base)
+    }
+
+    pub fn ip(&self) -> isize {
+        self.ip
+    }
+
+    pub fn mem(&self) -> &[i64] {
+        self.mem
+    }
+}
+
+/// An op that hands its parameters to a Rust closure
+pub struct HostCall {
+    params: Vec<LSPair>,
+    f: Arc<HostFn>,
+}
+
+impl OpCode for HostCall {
+    /// Host calls carry their closure, so they can only be built through `reg_host_call`
+    fn new(_reg: &ParamReg, _param: i64) -> Result<Box<dyn OpCode>, Error> {
+        Err(Error::UnboundHostCall)
+    }
+
+    fn execute(
+        &self,
+        ip: isize,
+        mem: &mut Memory,
+        _: &Option<Receiver<i64>>,
+        _: &mut Option<Sender<i64>>,
+        rel_base: &mut isize,
+    ) -> Result<isize, Error> {
+        (self.f)(&mut HostArgs {
+            ip,
+            mem,
+            rel_base: *rel_base,
+            params: &self.params,
+        })?;
+        Ok(self.params.len() as isize + 1)
+    }
+
+    /// Host calls take whatever opcode they are registered under
+    fn code() -> i64 {
+        0
+    }
+
+    /// The width depends on the registered parameter count; this is only the opcode itself
+    fn width() -> usize {
+        1
+    }
+}
+
+impl std::fmt::Debug for HostCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HOST/{}", self.params.len())
+    }
+}
+
+impl IntCodeMachine {
+    /// Register `opcode` as a call into `f`, taking `params` parameters in the usual modes. State
+    /// shared with the rest of the program can be captured by `f`, e.g. in an `Arc<Mutex<_>>`.
+    pub fn reg_host_call<F>(&mut self, opcode: i64, params: usize, f: F) -> Result<(), Error>
+    where
+        F: Fn(&mut HostArgs) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        let f: Arc<HostFn> = Arc::new(f);
+        self.extend_opcode(opcode, move |reg, param| {
+            let ps = decompose_param(param, params)?;
+            Ok(Box::new(HostCall {
+                params: ps
+                    .iter()
+                    .map(|m| reg.get(*m).cloned())
+                    .collect::<Result<Vec<LSPair>, Error>>()?,
+                f: f.clone(),
+            }) as Box<dyn OpCode>)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day9::build_machine;
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::sync::mpsc::channel;
+    use std::sync::Mutex;
+
+    #[test]
+    fn host_call() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let seen = log.clone();
+
+        // host(7, @6) then print @6
+        let mut m = build_machine(vec![150, 7, 6, 4, 6, 99, 0]);
+        m.reg_host_call(50, 2, move |args| {
+            let v = args.load(0)?;
+            seen.lock().unwrap().push(v);
+            args.store(1, v * 10)
+        })
+        .unwrap();
+        let (tx, rx) = channel();
+        m.wire_output(tx);
+        assert_eq!(m.run(), Ok(vec![150, 7, 6, 4, 6, 99, 70]));
+        assert_eq!(rx.recv(), Ok(70));
+        assert_eq!(*log.lock().unwrap(), vec![7]);
+    }
+
+    #[test]
+    fn stateful_ctor() {
+        // each call to opcode 60 stores the next number from a shared counter
+        let counter = Arc::new(AtomicI64::new(100));
+        let mut m = build_machine(vec![60, 5, 60, 6, 99, 0, 0]);
+        m.reg_host_call(60, 1, move |args| {
+            args.store(0, counter.fetch_add(1, Ordering::SeqCst))
+        })
+        .unwrap();
+        assert_eq!(m.run(), Ok(vec![60, 5, 60, 6, 99, 100, 101]));
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut m = build_machine(vec![99]);
+        assert_eq!(
+            m.reg_host_call(1, 0, |_| Ok(())),
+            Err(Error::OpcodeConflict(1))
+        );
+        assert_eq!(
+            m.extend_opcode(99, |_, _| Err(Error::BadParamMode)),
+            Err(Error::OpcodeConflict(99))
+        );
+        assert!(m.reg_host_call(42, 0, |_| Ok(())).is_ok());
+    }
+
+    #[test]
+    fn bad_param() {
+        let mut m = build_machine(vec![50, 0, 99]);
+        m.reg_host_call(50, 1, |args| args.load(1).map(|_| ()))
+            .unwrap();
+        assert_eq!(m.run(), Err(Error::BadParamMode));
+
+        // a mode digit for a second parameter the call does not take
+        let mut m = build_machine(vec![1150, 0, 99]);
+        m.reg_host_call(50, 1, |_| Ok(())).unwrap();
+        assert_eq!(m.run(), Err(Error::BadParamMode));
+    }
+
+    #[test]
+    fn unbound() {
+        assert_eq!(
+            HostCall::new(&ParamReg::new(), 0).err(),
+            Some(Error::UnboundHostCall)
+        );
+    }
+}
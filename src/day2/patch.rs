@@ -0,0 +1,204 @@
+use std::fmt;
+use std::fs::read_to_string;
+use std::str::FromStr;
+
+use super::{read_comma_file, Error, IntCodeMachine};
+
+/// Patches may grow a program, but not past this many cells
+pub const MAX_LEN: usize = 1 << 20;
+
+/// One changed memory cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edit {
+    pub addr: usize,
+    pub old: i64,
+    pub new: i64,
+}
+
+/// Changes to a program, each recording the value it expects to replace. Written one edit per
+/// line as `addr: old -> new`; `#` starts a comment. Memory past the end of a program counts as
+/// zero, so patches may also grow it, up to `MAX_LEN` cells.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Patch {
+    edits: Vec<Edit>,
+}
+
+impl Patch {
+    pub fn new(edits: Vec<Edit>) -> Self {
+        Patch { edits }
+    }
+
+    /// The edits that turn `base` into `target`
+    pub fn diff(base: &[i64], target: &[i64]) -> Self {
+        let cell = |mem: &[i64], addr| mem.get(addr).cloned().unwrap_or(0);
+        Patch::new(
+            (0..base.len().max(target.len()))
+                .filter(|a| cell(base, *a) != cell(target, *a))
+                .map(|addr| Edit {
+                    addr,
+                    old: cell(base, addr),
+                    new: cell(target, addr),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn load(filename: &str) -> Result<Self, Error> {
+        read_to_string(filename)?.parse()
+    }
+
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// Apply every edit, or none if any cell does not hold the value the patch expects or lies
+    /// past `MAX_LEN`
+    pub fn apply(&self, mem: &mut Vec<i64>) -> Result<(), Error> {
+        for e in &self.edits {
+            if e.addr >= MAX_LEN {
+                return Err(Error::PatchOutOfRange(e.addr));
+            }
+            if mem.get(e.addr).cloned().unwrap_or(0) != e.old {
+                return Err(Error::PatchMismatch(e.addr));
+            }
+        }
+        for e in &self.edits {
+            if e.addr >= mem.len() {
+                mem.resize(e.addr + 1, 0);
+            }
+            mem[e.addr] = e.new;
+        }
+        Ok(())
+    }
+
+    /// The patch that undoes this one
+    pub fn reversed(&self) -> Self {
+        Patch::new(
+            self.edits
+                .iter()
+                .map(|e| Edit {
+                    addr: e.addr,
+                    old: e.new,
+                    new: e.old,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for Patch {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut edits = Vec::new();
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (addr, change) = line.split_once(':').ok_or(Error::BadPatch)?;
+            let (old, new) = change.split_once("->").ok_or(Error::BadPatch)?;
+            edits.push(Edit {
+                addr: addr.trim().parse().map_err(|_| Error::BadPatch)?,
+                old: old.trim().parse()?,
+                new: new.trim().parse()?,
+            });
+        }
+        Ok(Patch::new(edits))
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for e in &self.edits {
+            writeln!(f, "{}: {} -> {}", e.addr, e.old, e.new)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read a program and apply a patch file to it
+pub fn read_patched(program: &str, patch: &str) -> Result<Vec<i64>, Error> {
+    let mut mem = read_comma_file(program)?;
+    Patch::load(patch)?.apply(&mut mem)?;
+    Ok(mem)
+}
+
+impl IntCodeMachine {
+    /// Apply `patch` to the freshly built machine's memory, e.g.
+    /// `build_profile("day9", mem)?.patched(&patch)?`
+    pub fn patched(mut self, patch: &Patch) -> Result<Self, Error> {
+        patch.apply(&mut self.mem.cells)?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let base = vec![1, 0, 0, 3, 99];
+        let target = vec![2, 0, 0, 3, 99, 0, 7];
+        let patch = Patch::diff(&base, &target);
+        assert_eq!(patch.to_string(), "0: 1 -> 2\n6: 0 -> 7\n");
+        assert_eq!(patch.to_string().parse(), Ok(patch.clone()));
+
+        let mut mem = base.clone();
+        patch.apply(&mut mem).unwrap();
+        assert_eq!(mem, target);
+        patch.reversed().apply(&mut mem).unwrap();
+        assert_eq!(&mem[..5], &base[..]);
+    }
+
+    #[test]
+    fn verified() {
+        let patch: Patch = "# free play\n0: 1 -> 2\n\n3: 3 -> 4 # trailing\n"
+            .parse()
+            .unwrap();
+        assert_eq!(patch.edits().len(), 2);
+        let mut mem = vec![1, 0, 0, 5];
+        assert_eq!(patch.apply(&mut mem), Err(Error::PatchMismatch(3)));
+        assert_eq!(mem, vec![1, 0, 0, 5]);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!("0 1 -> 2".parse::<Patch>(), Err(Error::BadPatch));
+        assert_eq!("0: 1 2".parse::<Patch>(), Err(Error::BadPatch));
+        assert_eq!("-1: 1 -> 2".parse::<Patch>(), Err(Error::BadPatch));
+        assert!("0: x -> 2".parse::<Patch>().is_err());
+    }
+
+    #[test]
+    fn out_of_range() {
+        let patch: Patch = "0: 1 -> 2\n18446744073709551615: 0 -> 1\n".parse().unwrap();
+        let mut mem = vec![1, 0, 0, 3, 99];
+        assert_eq!(
+            patch.apply(&mut mem),
+            Err(Error::PatchOutOfRange(usize::MAX))
+        );
+        assert_eq!(mem, vec![1, 0, 0, 3, 99]);
+    }
+
+    #[test]
+    fn builder() {
+        let patch: Patch = "1: 0 -> 4\n".parse().unwrap();
+        let m = crate::day2::build_profile("day2", vec![1, 0, 0, 3, 99])
+            .unwrap()
+            .patched(&patch)
+            .unwrap();
+        assert_eq!(m.run(), Ok(vec![1, 4, 0, 100, 99]));
+    }
+
+    #[test]
+    fn floor() {
+        // the walled-in floor the day 13 autopilot once needed
+        let mut mem = read_patched("input/day13.txt", "input/day13-floor.patch").unwrap();
+        let patch = Patch::load("input/day13-floor.patch").unwrap();
+        assert_eq!(patch.edits().len(), 39);
+        patch.reversed().apply(&mut mem).unwrap();
+        assert_eq!(mem, read_comma_file("input/day13.txt").unwrap());
+    }
+}
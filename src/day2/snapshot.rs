@@ -0,0 +1,195 @@
+use std::fs::File;
+use std::io::{Read, Write};
+
+use super::{Error, IntCodeMachine};
+use crate::{day5, day7, day9};
+
+/// Build a machine for the ISA profile called `isa`
+pub fn build_profile(isa: &str, mem: Vec<i64>) -> Result<IntCodeMachine, Error> {
+    match isa {
+        "day2" => Ok(IntCodeMachine::boot(mem)),
+        "day5" => Ok(day5::build_machine(mem)),
+        "day7" => Ok(day7::build_machine(mem)),
+        "day9" => Ok(day9::build_machine(mem)),
+        _ => Err(Error::UnknownIsa(isa.to_string())),
+    }
+}
+
+fn join(values: &[i64]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn split(values: &str) -> Result<Vec<i64>, Error> {
+    if values.is_empty() {
+        return Ok(Vec::new());
+    }
+    values
+        .split(',')
+        .map(|v| v.trim().parse::<i64>().map_err(|e| e.into()))
+        .collect()
+}
+
+impl IntCodeMachine {
+    /// A snapshot names the ISA rather than recording it, so refuse to save a machine whose
+    /// opcodes or parameter modes were registered after its profile was built, whether through
+    /// `reg_opcode`, `extend_opcode`, `reg_host_call` or `reg_param_mode`.
+    fn check_profile(&self) -> Result<(), Error> {
+        build_profile(self.isa, Vec::new())?;
+        if self.customized {
+            Err(Error::CustomIsa(self.isa.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Render the machine state, one `key value` pair per line. Values waiting in the wired input
+    /// are included and stay queued. Fails with `CustomIsa` if the machine's ISA is not exactly
+    /// its named profile, since loading would silently drop the difference.
+    ///
+    /// The pending values are read out of the input channel and sent back, so this needs exclusive
+    /// access to the input: nothing else, such as a neighbour in a `Cluster`, may send to the
+    /// machine while it is saved, or the queued values can be reordered.
+    pub fn save_state_string(&mut self) -> Result<String, Error> {
+        self.check_profile()?;
+        let mut state = format!(
+            "isa {}\nip {}\nrel_base {}\n",
+            self.isa, self.ip, self.rel_base
+        );
+        if let Some(rx) = &self.input {
+            let pending: Vec<i64> = rx.try_iter().collect();
+            if let Some(tx) = &self.user_input {
+                for v in &pending {
+                    tx.send(*v)?;
+                }
+            }
+            state.push_str(&format!("input {}\n", join(&pending)));
+        }
+        state.push_str(&format!("mem {}\n", join(&self.mem)));
+        Ok(state)
+    }
+
+    /// Rebuild a machine from the output of `save_state_string`. If the input was wired when the
+    /// state was saved, it is wired again with the pending values queued; use
+    /// `get_input_handle` to feed it further.
+    pub fn load_state_str(state: &str) -> Result<Self, Error> {
+        let (mut isa, mut ip, mut rel_base, mut input, mut mem) = (None, None, None, None, None);
+        for line in state.lines().filter(|l| !l.trim().is_empty()) {
+            let mut kv = line.splitn(2, ' ');
+            let key = kv.next();
+            let value = kv.next().unwrap_or("").trim();
+            match key {
+                Some("isa") => isa = Some(value.to_string()),
+                Some("ip") => ip = Some(value.parse::<isize>()?),
+                Some("rel_base") => rel_base = Some(value.parse::<isize>()?),
+                Some("input") => input = Some(split(value)?),
+                Some("mem") => mem = Some(split(value)?),
+                _ => return Err(Error::BadSnapshot),
+            }
+        }
+
+        let mut m = build_profile(
+            &isa.ok_or(Error::BadSnapshot)?,
+            mem.ok_or(Error::BadSnapshot)?,
+        )?;
+        m.ip = ip.ok_or(Error::BadSnapshot)?;
+        m.rel_base = rel_base.ok_or(Error::BadSnapshot)?;
+        if let Some(pending) = input {
+            let tx = m.wire_input();
+            for v in pending {
+                tx.send(v)?;
+            }
+        }
+        Ok(m)
+    }
+
+    /// See `save_state_string` for the access this needs
+    pub fn save_state(&mut self, filename: &str) -> Result<(), Error> {
+        File::create(filename)?.write_all(self.save_state_string()?.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load_state(filename: &str) -> Result<Self, Error> {
+        let mut state = String::new();
+        File::open(filename)?.read_to_string(&mut state)?;
+        Self::load_state_str(&state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day2::op::OpCode;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn round_trip() {
+        // add two inputs, output the sum
+        let program = vec![3, 11, 3, 12, 1, 11, 12, 11, 4, 11, 99, 0, 0];
+        let mut m = day9::build_machine(program);
+        let tx = m.wire_input();
+        tx.send(40).unwrap();
+        tx.send(2).unwrap();
+        m.step().unwrap();
+
+        let state = m.save_state_string().unwrap();
+        assert_eq!(
+            state,
+            "isa day9\nip 2\nrel_base 0\ninput 2\nmem 3,11,3,12,1,11,12,11,4,11,99,40,0\n"
+        );
+
+        let mut restored = IntCodeMachine::load_state_str(&state).unwrap();
+        assert_eq!(restored.isa(), "day9");
+        assert_eq!(restored.ip(), 2);
+        let (o_tx, o_rx) = channel();
+        restored.wire_output(o_tx);
+        assert!(restored.run().is_ok());
+        assert_eq!(o_rx.recv(), Ok(42));
+
+        // the original still has its input queued
+        let (o_tx, o_rx) = channel();
+        m.wire_output(o_tx);
+        assert!(m.run().is_ok());
+        assert_eq!(o_rx.recv(), Ok(42));
+    }
+
+    #[test]
+    fn custom_isa() {
+        let mut m = day9::build_machine(vec![99]);
+        m.reg_host_call(50, 0, |_| Ok(())).unwrap();
+        assert_eq!(
+            m.save_state_string(),
+            Err(Error::CustomIsa("day9".to_string()))
+        );
+
+        let mut m = IntCodeMachine::boot(vec![99]);
+        m.reg_param_mode(1, day5::immediate::load, day5::immediate::store);
+        assert_eq!(
+            m.save_state_string(),
+            Err(Error::CustomIsa("day2".to_string()))
+        );
+
+        // same opcode numbers as day5, but output replaced by day7's wired output
+        let mut m = day5::build_machine(vec![99]);
+        m.reg_opcode(day7::op::WiredOutput::code(), day7::op::WiredOutput::new);
+        assert_eq!(
+            m.save_state_string(),
+            Err(Error::CustomIsa("day5".to_string()))
+        );
+    }
+
+    #[test]
+    fn bad_state() {
+        assert_eq!(
+            IntCodeMachine::load_state_str("isa day9\nip 0\nrel_base 0\n").err(),
+            Some(Error::BadSnapshot)
+        );
+        assert_eq!(
+            IntCodeMachine::load_state_str("isa day42\nip 0\nrel_base 0\nmem 99\n").err(),
+            Some(Error::UnknownIsa("day42".to_string()))
+        );
+    }
+}
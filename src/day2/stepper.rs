@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use super::{Error, IntCodeMachine};
+
+/// Why [`Stepper::resume`] handed control back
+#[derive(Debug, PartialEq, Eq)]
+pub enum Yield {
+    /// The program wrote a value
+    Output(i64),
+    /// The program is about to read, and no input is queued
+    NeedsInput,
+    /// The program terminated
+    Halted,
+    /// The step budget ran out first
+    OutOfSteps,
+}
+
+/// Runs a wired machine on the calling thread, feeding queued input only when the program asks
+/// for it, so the caller always knows whether the machine is waiting on them.
+pub struct Stepper {
+    machine: IntCodeMachine,
+    input: Sender<i64>,
+    output: Receiver<i64>,
+    pending: VecDeque<i64>,
+    steps: u64,
+    halted: bool,
+}
+
+impl IntCodeMachine {
+    /// Whether the instruction under the IP is an input (opcode 3 in every profile)
+    pub fn awaiting_input(&self) -> bool {
+        self.mem
+            .get(self.ip as usize)
+            .map(|op| op % 100 == 3)
+            .unwrap_or(false)
+    }
+}
+
+impl Stepper {
+    pub fn new(mut machine: IntCodeMachine) -> Self {
+        let (tx, rx) = channel();
+        machine.wire_output(tx);
+        let input = machine.wire_input();
+        Stepper {
+            machine,
+            input,
+            output: rx,
+            pending: VecDeque::new(),
+            steps: 0,
+            halted: false,
+        }
+    }
+
+    /// Queue a value for the next input instruction
+    pub fn push_input(&mut self, value: i64) {
+        self.pending.push_back(value);
+    }
+
+    /// Run until the program outputs, needs input or terminates
+    pub fn resume(&mut self) -> Result<Yield, Error> {
+        self.resume_for(u64::MAX)
+    }
+
+    /// Like `resume`, but execute at most `max_steps` instructions
+    pub fn resume_for(&mut self, max_steps: u64) -> Result<Yield, Error> {
+        let mut budget = max_steps;
+        loop {
+            if let Ok(v) = self.output.try_recv() {
+                return Ok(Yield::Output(v));
+            }
+            if self.halted {
+                return Ok(Yield::Halted);
+            }
+            if budget == 0 {
+                return Ok(Yield::OutOfSteps);
+            }
+            if self.machine.awaiting_input() {
+                match self.pending.pop_front() {
+                    Some(v) => self.input.send(v)?,
+                    None => return Ok(Yield::NeedsInput),
+                }
+            }
+            budget -= 1;
+            self.steps += 1;
+            match self.machine.step() {
+                Ok(()) => {}
+                Err(Error::Terminated) => self.halted = true,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Number of instructions executed so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn machine(&self) -> &IntCodeMachine {
+        &self.machine
+    }
+
+    pub fn machine_mut(&mut self) -> &mut IntCodeMachine {
+        &mut self.machine
+    }
+
+    /// An independent copy of the machine with the same queued input and a zeroed step count
+    pub fn fork(&self) -> Self {
+        let mut s = Stepper::new(self.machine.fork());
+        s.pending = self.pending.clone();
+        s.halted = self.halted;
+        s
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day9::build_machine;
+
+    #[test]
+    fn yields() {
+        // echo one value, then halt
+        let mut s = Stepper::new(build_machine(vec![3, 5, 4, 5, 99, 0]));
+        assert_eq!(s.resume(), Ok(Yield::NeedsInput));
+        s.push_input(7);
+        assert_eq!(s.resume(), Ok(Yield::Output(7)));
+        assert_eq!(s.resume(), Ok(Yield::Halted));
+        assert_eq!(s.steps(), 3);
+    }
+
+    #[test]
+    fn budget_and_fork() {
+        // count up at @7 forever
+        let mut s = Stepper::new(build_machine(vec![1001, 7, 1, 7, 1105, 1, 0, 0]));
+        assert_eq!(s.resume_for(10), Ok(Yield::OutOfSteps));
+        assert_eq!(s.machine().mem()[7], 5);
+
+        let mut f = s.fork();
+        assert_eq!(f.resume_for(2), Ok(Yield::OutOfSteps));
+        assert_eq!(f.machine().mem()[7], 6);
+        assert_eq!(s.machine().mem()[7], 5);
+    }
+}
@@ -0,0 +1,360 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use super::conformance::{Engine, Failure};
+use super::{Error, Wired};
+
+/// Machine state the translated instructions operate on
+struct State {
+    mem: Vec<i64>,
+    rel_base: i64,
+    input: Option<Receiver<i64>>,
+    output: Option<Sender<i64>>,
+    user_input: Option<Sender<i64>>,
+    /// Address written by the instruction that just ran, if any
+    written: Option<usize>,
+}
+
+impl State {
+    fn read(&self, addr: i64) -> Result<i64, Error> {
+        self.mem
+            .get(addr as usize)
+            .cloned()
+            .ok_or(Error::MemoryError(addr as isize))
+    }
+
+    fn write(&mut self, addr: i64, value: i64) -> Result<(), Error> {
+        *self
+            .mem
+            .get_mut(addr as usize)
+            .ok_or(Error::MemoryError(addr as isize))? = value;
+        self.written = Some(addr as usize);
+        Ok(())
+    }
+}
+
+/// Where control goes after an instruction
+enum Flow {
+    Next(usize),
+    Jump(i64),
+}
+
+type Load = Box<dyn Fn(&State) -> Result<i64, Error> + Send + Sync>;
+type Store = Box<dyn Fn(&mut State, i64) -> Result<(), Error> + Send + Sync>;
+type Instr = Box<dyn Fn(&mut State) -> Result<Flow, Error> + Send + Sync>;
+
+fn load(mode: i64, word: i64) -> Result<Load, Error> {
+    match mode {
+        0 => Ok(Box::new(move |s: &State| s.read(word))),
+        1 => Ok(Box::new(move |_: &State| Ok(word))),
+        2 => Ok(Box::new(move |s: &State| {
+            s.read(s.rel_base.checked_add(word).ok_or(Error::Overflow)?)
+        })),
+        _ => Err(Error::BadParamMode),
+    }
+}
+
+fn store(mode: i64, word: i64) -> Result<Store, Error> {
+    match mode {
+        0 => Ok(Box::new(move |s: &mut State, v| s.write(word, v))),
+        2 => Ok(Box::new(move |s: &mut State, v| {
+            let addr = s.rel_base.checked_add(word).ok_or(Error::Overflow)?;
+            s.write(addr, v)
+        })),
+        _ => Err(Error::BadParamMode),
+    }
+}
+
+fn binary(
+    modes: [i64; 3],
+    words: [i64; 3],
+    f: fn(i64, i64) -> Option<i64>,
+) -> Result<Instr, Error> {
+    let (l, r, dest) = (
+        load(modes[0], words[0])?,
+        load(modes[1], words[1])?,
+        store(modes[2], words[2])?,
+    );
+    Ok(Box::new(move |s: &mut State| {
+        let v = f(l(s)?, r(s)?).ok_or(Error::Overflow)?;
+        dest(s, v)?;
+        Ok(Flow::Next(4))
+    }))
+}
+
+fn jump(modes: [i64; 3], words: [i64; 3], if_zero: bool) -> Result<Instr, Error> {
+    let (cond, dest) = (load(modes[0], words[0])?, load(modes[1], words[1])?);
+    Ok(Box::new(move |s: &mut State| {
+        if (cond(s)? == 0) == if_zero {
+            Ok(Flow::Jump(dest(s)?))
+        } else {
+            Ok(Flow::Next(3))
+        }
+    }))
+}
+
+/// Translate the instruction at `ip` into a closure with its operands already resolved
+fn translate(mem: &[i64], ip: usize) -> Result<Instr, Error> {
+    let word = *mem.get(ip).ok_or(Error::MemoryError(ip as isize))?;
+    let modes = [word / 100 % 10, word / 1000 % 10, word / 10000 % 10];
+    let mut words = [0; 3];
+    for (k, w) in words.iter_mut().enumerate() {
+        *w = mem.get(ip + k + 1).cloned().unwrap_or(0);
+    }
+    // like decompose_param, refuse more mode digits than the instruction is wide
+    let width = match word % 100 {
+        1 | 2 | 7 | 8 => Some(4),
+        5 | 6 => Some(3),
+        3 | 4 | 9 => Some(2),
+        _ => None,
+    };
+    if width.is_some_and(|w| word / 100 >= 10_i64.pow(w)) {
+        return Err(Error::BadParamMode);
+    }
+    match word % 100 {
+        1 => binary(modes, words, i64::checked_add),
+        2 => binary(modes, words, i64::checked_mul),
+        7 => binary(modes, words, |a, b| Some((a < b) as i64)),
+        8 => binary(modes, words, |a, b| Some((a == b) as i64)),
+        5 => jump(modes, words, false),
+        6 => jump(modes, words, true),
+        3 => {
+            let dest = store(modes[0], words[0])?;
+            Ok(Box::new(move |s: &mut State| {
+                let v = s.input.as_ref().ok_or(Error::InputFailed)?.recv()?;
+                dest(s, v)?;
+                Ok(Flow::Next(2))
+            }))
+        }
+        4 => {
+            let src = load(modes[0], words[0])?;
+            Ok(Box::new(move |s: &mut State| {
+                let v = src(s)?;
+                s.output.as_ref().ok_or(Error::OutputFailed)?.send(v)?;
+                Ok(Flow::Next(2))
+            }))
+        }
+        9 => {
+            let adj = load(modes[0], words[0])?;
+            Ok(Box::new(move |s: &mut State| {
+                s.rel_base = s.rel_base.checked_add(adj(s)?).ok_or(Error::Overflow)?;
+                Ok(Flow::Next(2))
+            }))
+        }
+        99 => Ok(Box::new(|_: &mut State| Err(Error::Terminated))),
+        op => Err(Error::BadOpcode(op)),
+    }
+}
+
+/// Alternative to `IntCodeMachine` for the full day 9 ISA. Each instruction is translated once,
+/// when first reached, into a closure with its operands resolved; writes into translated code
+/// throw the affected translations away so they are redone on the next visit.
+pub struct ThreadedMachine {
+    ip: usize,
+    code: Vec<Option<Instr>>,
+    state: State,
+}
+
+impl ThreadedMachine {
+    pub fn boot(mem: Vec<i64>) -> Self {
+        ThreadedMachine {
+            ip: 0,
+            code: (0..mem.len()).map(|_| None).collect(),
+            state: State {
+                mem,
+                rel_base: 0,
+                input: None,
+                output: None,
+                user_input: None,
+                written: None,
+            },
+        }
+    }
+
+    /// Pick up a program part-way through, with its I/O already wired
+    pub fn resume(
+        mem: Vec<i64>,
+        ip: usize,
+        rel_base: i64,
+        input: Receiver<i64>,
+        output: Sender<i64>,
+    ) -> Self {
+        let mut m = ThreadedMachine::boot(mem);
+        m.ip = ip;
+        m.state.rel_base = rel_base;
+        m.state.input = Some(input);
+        m.state.output = Some(output);
+        m
+    }
+
+    pub fn step(&mut self) -> Result<(), Error> {
+        let ip = self.ip;
+        if self
+            .code
+            .get(ip)
+            .ok_or(Error::MemoryError(ip as isize))?
+            .is_none()
+        {
+            self.code[ip] = Some(translate(&self.state.mem, ip)?);
+        }
+        let orig_ip_val = self.state.mem[ip];
+        let flow = match &self.code[ip] {
+            Some(instr) => instr(&mut self.state)?,
+            None => return Err(Error::MemoryError(ip as isize)),
+        };
+
+        if let Some(addr) = self.state.written.take() {
+            for a in addr.saturating_sub(3)..=addr {
+                self.code[a] = None;
+            }
+        }
+
+        let next = if self.state.mem[ip] != orig_ip_val {
+            // the value under the IP was written - jump to that address, as IntCodeMachine does
+            self.state.mem[ip]
+        } else {
+            match flow {
+                Flow::Next(width) => (ip + width) as i64,
+                Flow::Jump(dest) => dest,
+            }
+        };
+        if next < 0 {
+            return Err(Error::MemoryError(next as isize));
+        }
+        self.ip = next as usize;
+        Ok(())
+    }
+
+    pub fn run(mut self) -> Result<Vec<i64>, Error> {
+        loop {
+            match self.step() {
+                Ok(()) => continue,
+                Err(Error::Terminated) => break Ok(self.state.mem),
+                Err(e) => break Err(e),
+            }
+        }
+    }
+
+    pub fn wire_input(&mut self) -> Sender<i64> {
+        let (tx, rx) = channel();
+        self.state.user_input = Some(tx.clone());
+        self.state.input = Some(rx);
+        tx
+    }
+
+    pub fn wire_output(&mut self, tx: Sender<i64>) {
+        self.state.output = Some(tx);
+    }
+}
+
+impl Wired for ThreadedMachine {
+    fn wire_input(&mut self) -> Sender<i64> {
+        ThreadedMachine::wire_input(self)
+    }
+
+    fn wire_output(&mut self, tx: Sender<i64>) {
+        ThreadedMachine::wire_output(self, tx)
+    }
+
+    fn run(self) -> Result<Vec<i64>, Error> {
+        ThreadedMachine::run(self)
+    }
+}
+
+/// Instructions a conformance case may execute on the threaded engine
+const STEP_BUDGET: u64 = 1_000_000;
+
+/// The profile `ThreadedMachine` implements
+pub const ISA: &str = "day9";
+
+/// `ThreadedMachine` as a conformance engine. It only has the full day 9 ISA, so cases for the
+/// narrower profiles fail with `UnknownIsa` rather than passing on a different ISA.
+pub struct Threaded;
+
+impl Engine for Threaded {
+    fn execute(
+        &self,
+        isa: &str,
+        program: Vec<i64>,
+        input: &[i64],
+    ) -> Result<(Vec<i64>, Vec<i64>), Failure> {
+        if isa != ISA {
+            return Err(Failure::Machine(Error::UnknownIsa(isa.to_string())));
+        }
+        let mut m = ThreadedMachine::boot(program);
+        let tx = m.wire_input();
+        for v in input {
+            tx.send(*v).map_err(Error::from)?;
+        }
+        // with every sender gone, reading past the queued input fails instead of blocking
+        drop(tx);
+        m.state.user_input = None;
+        let (o_tx, o_rx) = channel();
+        m.wire_output(o_tx);
+
+        for _ in 0..STEP_BUDGET {
+            match m.step() {
+                Ok(()) => {}
+                Err(Error::Terminated) => return Ok((o_rx.try_iter().collect(), m.state.mem)),
+                Err(Error::InputFailed) => return Err(Failure::Machine(Error::NeedsInput)),
+                Err(e) => return Err(Failure::Machine(e)),
+            }
+        }
+        Err(Failure::Runaway)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day2::conformance::{load_corpus, run_corpus};
+    use crate::day2::read_comma_file;
+    use crate::day2::IntCodeMachine;
+
+    #[test]
+    fn corpus() {
+        let cases = load_corpus("input/intcode.corpus").unwrap();
+        let (day9, other): (Vec<_>, Vec<_>) = cases.into_iter().partition(|c| c.isa == ISA);
+        assert!(!day9.is_empty());
+        assert_eq!(run_corpus(&day9, &Threaded), vec![]);
+        let refused = run_corpus(&other, &Threaded);
+        assert_eq!(refused.len(), other.len());
+        for (_, failure) in refused {
+            assert!(matches!(failure, Failure::Machine(Error::UnknownIsa(_))));
+        }
+    }
+
+    #[test]
+    fn day2_matches_interpreter() {
+        let mut data = read_comma_file("input/day2.txt").unwrap();
+        data[1] = 12;
+        data[2] = 2;
+        assert_eq!(
+            ThreadedMachine::boot(data.clone()).run(),
+            IntCodeMachine::boot(data).run()
+        );
+    }
+
+    #[test]
+    fn self_modifying() {
+        // add @12 = 3 + 4, patch that add into a multiply, then jump back to it
+        let mut m = ThreadedMachine::boot(vec![1101, 3, 4, 12, 1101, 1102, 0, 0, 1105, 1, 0, 0, 0]);
+        m.step().unwrap();
+        assert_eq!(m.state.mem[12], 7);
+        assert!(m.code[0].is_some());
+        m.step().unwrap();
+        assert!(m.code[0].is_none());
+        m.step().unwrap();
+        m.step().unwrap();
+        assert_eq!(m.state.mem[12], 12);
+    }
+
+    #[test]
+    fn overwrite_ip() {
+        // writing over the running opcode sends the IP to the written value
+        let program = vec![1101, 4, 0, 0, 99];
+        assert_eq!(
+            ThreadedMachine::boot(program.clone()).run(),
+            crate::day9::build_machine(program).run()
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use super::{Error, IntCodeMachine};
+
+/// One executed instruction, with everything needed to undo it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// Instruction pointer before the step
+    pub ip: isize,
+    /// Change the step made to the relative base
+    pub rel_delta: isize,
+    /// Memory cells the step overwrote, paired with their previous values
+    pub writes: Vec<(usize, i64)>,
+}
+
+/// Per-step history of a machine, oldest step first
+#[derive(Debug, Default)]
+pub struct ExecLog {
+    entries: Vec<Entry>,
+}
+
+impl ExecLog {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Index of the most recent step that overwrote `addr`
+    pub fn last_write_to(&self, addr: usize) -> Option<usize> {
+        self.entries
+            .iter()
+            .rposition(|e| e.writes.iter().any(|(a, _)| *a == addr))
+    }
+
+    pub(super) fn push(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+}
+
+impl IntCodeMachine {
+    /// Start recording every step so that it can be undone later. Input already consumed from the
+    /// wired input is not given back by a rewind.
+    pub fn enable_exec_log(&mut self) {
+        if self.exec_log.is_none() {
+            self.exec_log = Some(ExecLog::default());
+        }
+    }
+
+    pub fn exec_log(&self) -> Option<&ExecLog> {
+        self.exec_log.as_ref()
+    }
+
+    /// Undo the most recent step
+    pub fn step_back(&mut self) -> Result<(), Error> {
+        let entry = self
+            .exec_log
+            .as_mut()
+            .and_then(|log| log.entries.pop())
+            .ok_or(Error::NoHistory)?;
+        for (addr, old) in entry.writes.into_iter().rev() {
+            self.mem.write(addr as isize, old)?;
+        }
+        self.rel_base -= entry.rel_delta;
+        self.ip = entry.ip;
+        Ok(())
+    }
+
+    /// Undo steps until only the first `step` remain, i.e. until the machine is about to execute
+    /// step number `step` again
+    pub fn rewind_to(&mut self, step: usize) -> Result<(), Error> {
+        let len = self.exec_log.as_ref().ok_or(Error::NoHistory)?.len();
+        if step > len {
+            return Err(Error::NoHistory);
+        }
+        for _ in step..len {
+            self.step_back()?;
+        }
+        Ok(())
+    }
+
+    /// Rewind to just before the step that last wrote `addr`, returning that step's index
+    pub fn rewind_to_last_write(&mut self, addr: usize) -> Result<usize, Error> {
+        let step = self
+            .exec_log
+            .as_ref()
+            .and_then(|log| log.last_write_to(addr))
+            .ok_or(Error::NoHistory)?;
+        self.rewind_to(step)?;
+        Ok(step)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day9::build_machine;
+
+    #[test]
+    fn step_back() {
+        let program = vec![1, 9, 9, 9, 1, 9, 9, 9, 99, 1];
+        let mut m = IntCodeMachine::boot(program.clone());
+        m.enable_exec_log();
+        m.step().unwrap();
+        m.step().unwrap();
+        assert_eq!(m.mem()[9], 4);
+        assert_eq!(m.ip(), 8);
+
+        m.step_back().unwrap();
+        assert_eq!(m.mem()[9], 2);
+        assert_eq!(m.ip(), 4);
+
+        m.rewind_to(0).unwrap();
+        assert_eq!(m.mem(), program.as_slice());
+        assert_eq!(m.ip(), 0);
+        assert_eq!(m.step_back(), Err(Error::NoHistory));
+    }
+
+    #[test]
+    fn rel_base() {
+        let program = vec![109, 5, 21101, 1, 2, 0, 99];
+        let mut m = build_machine(program.clone());
+        m.enable_exec_log();
+        m.step().unwrap();
+        m.step().unwrap();
+        assert_eq!(m.mem()[5], 3);
+        assert_eq!(m.exec_log().unwrap().entries()[0].rel_delta, 5);
+
+        m.step_back().unwrap();
+        assert_eq!(m.mem(), program.as_slice());
+        assert_eq!(m.rel_base(), 5);
+
+        m.step_back().unwrap();
+        assert_eq!(m.rel_base(), 0);
+    }
+
+    #[test]
+    fn last_write() {
+        // @9 += 1, three times
+        let program = vec![1001, 9, 1, 9, 1006, 10, 0, 99, 0, 0, 0];
+        let mut m = build_machine(program);
+        m.enable_exec_log();
+        assert_eq!(m.rewind_to_last_write(9), Err(Error::NoHistory));
+        for _ in 0..6 {
+            m.step().unwrap();
+        }
+        assert_eq!(m.mem()[9], 3);
+
+        assert_eq!(m.rewind_to_last_write(9), Ok(4));
+        assert_eq!(m.ip(), 0);
+        assert_eq!(m.mem()[9], 2);
+    }
+
+    #[test]
+    fn same_value_write() {
+        // @9 = 5, then @9 = 5 again through a relative parameter
+        let program = vec![1101, 2, 3, 9, 21101, 4, 1, 9, 99, 0];
+        let mut m = build_machine(program);
+        m.enable_exec_log();
+        m.step().unwrap();
+        m.step().unwrap();
+        assert_eq!(m.exec_log().unwrap().entries()[1].writes, vec![(9, 5)]);
+
+        assert_eq!(m.rewind_to_last_write(9), Ok(1));
+        assert_eq!(m.ip(), 4);
+        assert_eq!(m.mem()[9], 5);
+    }
+
+    #[test]
+    fn wide_host_call() {
+        // host call with five parameters, storing through the last one to @8
+        let program = vec![50, 0, 0, 0, 0, 8, 99, 0, 7];
+        let mut m = build_machine(program.clone());
+        m.reg_host_call(50, 5, |args| args.store(4, 42)).unwrap();
+        m.enable_exec_log();
+        m.step().unwrap();
+        assert_eq!(m.mem()[8], 42);
+        assert_eq!(m.exec_log().unwrap().entries()[0].writes, vec![(8, 7)]);
+
+        m.step_back().unwrap();
+        assert_eq!(m.mem(), program.as_slice());
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::sync::mpsc::{Receiver, Sender};
+
+use super::{read_comma_file, Error, ThreadedMachine};
+
+/// An instruction found by walking the program image from its entry point
+#[derive(Debug)]
+struct Instr {
+    word: i64,
+    op: i64,
+    modes: [i64; 3],
+    args: [i64; 3],
+    width: usize,
+}
+
+fn decode(program: &[i64], addr: usize) -> Option<Instr> {
+    let word = *program.get(addr)?;
+    let op = word % 100;
+    let modes = [word / 100 % 10, word / 1000 % 10, word / 10000 % 10];
+    let (width, stores) = match op {
+        1 | 2 | 7 | 8 => (4, Some(2)),
+        3 => (2, Some(0)),
+        4 | 9 => (2, None),
+        5 | 6 => (3, None),
+        99 => (1, None),
+        _ => return None,
+    };
+    if !(0..100_000).contains(&word) || addr + width > program.len() {
+        return None;
+    }
+    for (p, mode) in modes.iter().enumerate().take(width - 1) {
+        let ok = if Some(p) == stores {
+            *mode == 0 || *mode == 2
+        } else {
+            *mode <= 2
+        };
+        if !ok {
+            return None;
+        }
+    }
+    let mut args = [0; 3];
+    args[..width - 1].copy_from_slice(&program[addr + 1..addr + width]);
+    Some(Instr {
+        word,
+        op,
+        modes,
+        args,
+        width,
+    })
+}
+
+/// Everything reachable from address 0, plus the block leaders: jump targets, the instruction
+/// after each jump, and any immediate add/mul operand that lands on code (return addresses)
+struct Analysis {
+    code: BTreeMap<usize, Instr>,
+    leaders: BTreeSet<usize>,
+}
+
+fn analyse(program: &[i64]) -> Analysis {
+    let mut code: BTreeMap<usize, Instr> = BTreeMap::new();
+    let mut leaders = BTreeSet::new();
+    leaders.insert(0);
+    let mut work = vec![0];
+    // constants that might be code addresses are only tried once real control flow is exhausted,
+    // so that a misaligned guess never shadows an instruction that is actually executed
+    let mut guesses = Vec::new();
+
+    while let Some(addr) = work.pop().or_else(|| guesses.pop()) {
+        if code.contains_key(&addr) {
+            continue;
+        }
+        let instr = match decode(program, addr) {
+            Some(i) => i,
+            None => continue,
+        };
+        let overlaps = code
+            .range(..addr + instr.width)
+            .next_back()
+            .is_some_and(|(a, i)| a + i.width > addr);
+        if overlaps {
+            continue;
+        }
+
+        let next = addr + instr.width;
+        match instr.op {
+            99 => {}
+            5 | 6 => {
+                if instr.modes[1] == 1 && instr.args[1] >= 0 {
+                    let target = instr.args[1] as usize;
+                    leaders.insert(target);
+                    work.push(target);
+                }
+                let always = instr.modes[0] == 1 && (instr.args[0] != 0) == (instr.op == 5);
+                if !always {
+                    leaders.insert(next);
+                    work.push(next);
+                }
+            }
+            1 | 2 => {
+                for p in 0..2 {
+                    if instr.modes[p] == 1 && instr.args[p] >= 0 {
+                        guesses.push(instr.args[p] as usize);
+                    }
+                }
+                work.push(next);
+            }
+            _ => work.push(next),
+        }
+        code.insert(addr, instr);
+    }
+
+    let guessed: Vec<usize> = code
+        .values()
+        .filter(|i| matches!(i.op, 1 | 2))
+        .flat_map(|i| {
+            (0..2)
+                .filter(move |p| i.modes[*p] == 1 && i.args[*p] >= 0)
+                .map(move |p| i.args[p] as usize)
+        })
+        .collect();
+    leaders.extend(guessed);
+    leaders.retain(|a| code.contains_key(a));
+    Analysis { code, leaders }
+}
+
+/// Address ranges covered by decoded instructions
+fn code_ranges(code: &BTreeMap<usize, Instr>) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (addr, instr) in code {
+        let end = addr + instr.width - 1;
+        match ranges.last_mut() {
+            Some(r) if r.1 + 1 == *addr => r.1 = end,
+            _ => ranges.push((*addr, end)),
+        }
+    }
+    ranges
+}
+
+fn load_expr(mode: i64, arg: i64) -> String {
+    match mode {
+        0 => format!("load(&mem, {})?", arg),
+        1 => format!("{}", arg),
+        _ => format!("load(&mem, add(rel, {})?)?", arg),
+    }
+}
+
+/// Emit the store of `value` through parameter `p`, bailing out to the fallback if it lands on
+/// translated code. Returns whether control can carry on in the translation.
+fn emit_store(
+    out: &mut String,
+    a: &Analysis,
+    addr: usize,
+    instr: &Instr,
+    p: usize,
+    value: &str,
+) -> Result<bool, std::fmt::Error> {
+    let resume = format!(
+        "ip = resume_at(&mem, {}, {}, {});",
+        addr,
+        instr.word,
+        addr + instr.width
+    );
+    let bail = "return fallback(mem, ip, rel, input, output);";
+    let arg = instr.args[p];
+    if instr.modes[p] == 0 {
+        writeln!(out, "                store(&mut mem, {}, {})?;", arg, value)?;
+        let hits_code = arg >= 0
+            && a.code
+                .range(..=arg as usize)
+                .next_back()
+                .is_some_and(|(ca, ci)| ca + ci.width > arg as usize);
+        if hits_code {
+            writeln!(out, "                {}", resume)?;
+            writeln!(out, "                {}", bail)?;
+            return Ok(false);
+        }
+    } else {
+        writeln!(out, "                let d = add(rel, {})?;", arg)?;
+        writeln!(out, "                store(&mut mem, d, {})?;", value)?;
+        writeln!(out, "                if is_code(d) {{")?;
+        writeln!(out, "                    {}", resume)?;
+        writeln!(out, "                    {}", bail)?;
+        writeln!(out, "                }}")?;
+    }
+    Ok(true)
+}
+
+/// Emit one instruction, returning whether control can fall through to the next one
+fn emit_instr(
+    out: &mut String,
+    a: &Analysis,
+    addr: usize,
+    instr: &Instr,
+) -> Result<bool, std::fmt::Error> {
+    let l = |p: usize| load_expr(instr.modes[p], instr.args[p]);
+    writeln!(
+        out,
+        "                // {}: {:?}",
+        addr,
+        &instr.args[..instr.width - 1]
+    )?;
+    match instr.op {
+        1 | 2 | 7 | 8 => {
+            let value = match instr.op {
+                1 => format!("add({}, {})?", l(0), l(1)),
+                2 => format!("mul({}, {})?", l(0), l(1)),
+                7 => format!("({} < {}) as i64", l(0), l(1)),
+                _ => format!("({} == {}) as i64", l(0), l(1)),
+            };
+            writeln!(out, "                let v = {};", value)?;
+            return emit_store(out, a, addr, instr, 2, "v");
+        }
+        3 => {
+            writeln!(out, "                let v = input.recv()?;")?;
+            return emit_store(out, a, addr, instr, 0, "v");
+        }
+        4 => writeln!(out, "                output.send({})?;", l(0))?,
+        9 => writeln!(out, "                rel = add(rel, {})?;", l(0))?,
+        5 | 6 => {
+            let always = instr.modes[0] == 1 && (instr.args[0] != 0) == (instr.op == 5);
+            if always {
+                writeln!(out, "                ip = {};", l(1))?;
+                writeln!(out, "                continue;")?;
+                return Ok(false);
+            }
+            let cmp = if instr.op == 5 { "!=" } else { "==" };
+            writeln!(out, "                if {} {} 0 {{", l(0), cmp)?;
+            writeln!(out, "                    ip = {};", l(1))?;
+            writeln!(out, "                    continue;")?;
+            writeln!(out, "                }}")?;
+        }
+        _ => {
+            writeln!(out, "                return Ok(mem);")?;
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Translate `program` into the source of a Rust module exposing `pub fn <name>`, which runs the
+/// program over memory that starts as the program image (plus any padding the caller adds).
+///
+/// Code reachable from address 0 becomes straight-line blocks in a `match` over the IP. A write
+/// into translated code, or a jump somewhere the translation doesn't cover, hands the rest of the
+/// run to `ThreadedMachine`.
+pub fn transpile(name: &str, program: &[i64]) -> String {
+    emit(name, program).expect("formatting into a String")
+}
+
+fn emit(name: &str, program: &[i64]) -> Result<String, std::fmt::Error> {
+    let a = analyse(program);
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "// @generated by `aoc2019 aot` from a {} word program",
+        program.len()
+    )?;
+    writeln!(
+        out,
+        "#![allow(unused_mut, unreachable_code, dead_code, clippy::all)]"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "use std::sync::mpsc::{{Receiver, Sender}};")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "use crate::day2::transpile::{{add, fallback, load, mul, resume_at, store}};"
+    )?;
+    writeln!(out, "use crate::day2::Error;")?;
+    writeln!(out)?;
+
+    let ranges = code_ranges(&a.code)
+        .iter()
+        .map(|(s, e)| format!("{}..={}", s, e))
+        .collect::<Vec<String>>();
+    writeln!(out, "fn is_code(addr: i64) -> bool {{")?;
+    writeln!(out, "    matches!(addr, {})", ranges.join(" | "))?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+
+    writeln!(out, "pub fn {}(", name)?;
+    writeln!(out, "    mut mem: Vec<i64>,")?;
+    writeln!(out, "    input: Receiver<i64>,")?;
+    writeln!(out, "    output: Sender<i64>,")?;
+    writeln!(out, ") -> Result<Vec<i64>, Error> {{")?;
+    writeln!(out, "    let mut ip: i64 = 0;")?;
+    writeln!(out, "    let mut rel: i64 = 0;")?;
+    writeln!(out, "    loop {{")?;
+    writeln!(out, "        match ip {{")?;
+    for leader in &a.leaders {
+        writeln!(out, "            {} => {{", leader)?;
+        let mut addr = *leader;
+        loop {
+            let instr = &a.code[&addr];
+            if !emit_instr(&mut out, &a, addr, instr)? {
+                break;
+            }
+            addr += instr.width;
+            if a.leaders.contains(&addr) || !a.code.contains_key(&addr) {
+                writeln!(out, "                ip = {};", addr)?;
+                break;
+            }
+        }
+        writeln!(out, "            }}")?;
+    }
+    writeln!(
+        out,
+        "            _ => return fallback(mem, ip, rel, input, output),"
+    )?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(out)
+}
+
+/// Transpile the comma-separated program in `filename`
+pub fn transpile_file(name: &str, filename: &str) -> Result<String, Error> {
+    Ok(transpile(name, &read_comma_file(filename)?))
+}
+
+/// Read a word for transpiled code
+pub fn load(mem: &[i64], addr: i64) -> Result<i64, Error> {
+    if addr < 0 {
+        return Err(Error::MemoryError(addr as isize));
+    }
+    mem.get(addr as usize)
+        .cloned()
+        .ok_or(Error::MemoryError(addr as isize))
+}
+
+/// Write a word for transpiled code
+pub fn store(mem: &mut [i64], addr: i64, value: i64) -> Result<(), Error> {
+    if addr < 0 {
+        return Err(Error::MemoryError(addr as isize));
+    }
+    *mem.get_mut(addr as usize)
+        .ok_or(Error::MemoryError(addr as isize))? = value;
+    Ok(())
+}
+
+/// Add for transpiled code
+pub fn add(a: i64, b: i64) -> Result<i64, Error> {
+    a.checked_add(b).ok_or(Error::Overflow)
+}
+
+/// Multiply for transpiled code
+pub fn mul(a: i64, b: i64) -> Result<i64, Error> {
+    a.checked_mul(b).ok_or(Error::Overflow)
+}
+
+/// Where execution continues after the instruction at `addr`, originally `word`, wrote into code:
+/// like `IntCodeMachine`, overwriting the opcode under the IP jumps to the written value
+pub fn resume_at(mem: &[i64], addr: usize, word: i64, next: usize) -> i64 {
+    if mem[addr] != word {
+        mem[addr]
+    } else {
+        next as i64
+    }
+}
+
+/// Run the rest of the program on `ThreadedMachine` once the translation can't be trusted
+pub fn fallback(
+    mem: Vec<i64>,
+    ip: i64,
+    rel_base: i64,
+    input: Receiver<i64>,
+    output: Sender<i64>,
+) -> Result<Vec<i64>, Error> {
+    if ip < 0 {
+        return Err(Error::MemoryError(ip as isize));
+    }
+    ThreadedMachine::resume(mem, ip as usize, rel_base, input, output).run()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn blocks() {
+        // 0: if @9 != 0 goto 6; 3: out 1; 5: halt; 6: out 2; 8: halt
+        let a = analyse(&[1005, 9, 6, 104, 1, 99, 104, 2, 99, 0]);
+        assert_eq!(
+            a.code.keys().cloned().collect::<Vec<_>>(),
+            vec![0, 3, 5, 6, 8]
+        );
+        assert_eq!(a.leaders.iter().cloned().collect::<Vec<_>>(), vec![0, 3, 6]);
+        assert_eq!(code_ranges(&a.code), vec![(0, 8)]);
+    }
+
+    #[test]
+    fn return_addresses() {
+        // push return address 7, call 10, which jumps back through @0
+        let a = analyse(&[
+            1101, 7, 0, 0, 1105, 1, 10, 104, 0, 99, 106, 0, 0, 104, 5, 99,
+        ]);
+        assert!(a.leaders.contains(&7));
+        assert!(a.code.contains_key(&9));
+        // 10 is a jump through memory; it never reaches the code after it
+        assert!(!a.code.contains_key(&13));
+    }
+
+    #[test]
+    fn self_modifying() {
+        let src = transpile("f", &[1101, 1102, 0, 4, 1101, 3, 4, 9, 99, 0]);
+        // the patching add ends its block by handing over to the fallback
+        assert!(src.contains(concat!(
+            "                ip = resume_at(&mem, 0, 1101, 4);\n",
+            "                return fallback(mem, ip, rel, input, output);\n",
+            "            }\n",
+        )));
+    }
+
+    #[test]
+    fn checked_in() {
+        let program = read_comma_file("input/day9.txt").unwrap();
+        assert_eq!(
+            transpile("boost", &program),
+            std::fs::read_to_string("src/day9/boost.rs").unwrap()
+        );
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+
+use crate::day2;
+use crate::day2::{read_comma_file, Stepper, Yield};
+use crate::day9::build_machine;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The Intcode machine failed
+    Machine(day2::Error),
+    /// The droid said something that isn't a room description
+    Unrecognized(String),
+    /// Exploration never reached the pressure-sensitive floor
+    NoCheckpoint,
+    /// No combination of items got past the floor
+    NoPassword,
+    /// Too many items held to try every combination of them
+    TooManyItems(usize),
+}
+
+impl From<day2::Error> for Error {
+    fn from(e: day2::Error) -> Self {
+        Error::Machine(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Machine(e.into())
+    }
+}
+
+/// Instructions a single command may take before the droid is considered stuck
+const STEP_BUDGET: u64 = 1_000_000;
+
+const CHECKPOINT: &str = "Security Checkpoint";
+
+/// Most items `crack` will try every combination of
+const MAX_HELD: usize = 20;
+
+/// What a room description tells us
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Room {
+    name: String,
+    doors: Vec<String>,
+    items: Vec<String>,
+}
+
+/// How the droid's reply to a command ended
+#[derive(Debug, PartialEq, Eq)]
+enum Ending {
+    Prompt,
+    Halted,
+    Stuck,
+}
+
+/// Parse the last room description in `text`
+fn parse_room(text: &str) -> Option<Room> {
+    let start = text.rfind("== ")?;
+    let mut room = Room::default();
+    let mut list = None;
+    for line in text[start..].lines() {
+        if line.starts_with("== ") {
+            room.name = line.trim_matches(|c| c == '=' || c == ' ').to_string();
+        } else if line.starts_with("Doors here lead") {
+            list = Some(&mut room.doors);
+        } else if line.starts_with("Items here") {
+            list = Some(&mut room.items);
+        } else if let Some(entry) = line.strip_prefix("- ") {
+            if let Some(l) = list.as_mut() {
+                l.push(entry.to_string());
+            }
+        } else {
+            list = None;
+        }
+    }
+    Some(room)
+}
+
+fn opposite(dir: &str) -> &'static str {
+    match dir {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        _ => "east",
+    }
+}
+
+fn send_line(droid: &mut Stepper, line: &str) {
+    for b in line.bytes().chain(std::iter::once(b'\n')) {
+        droid.push_input(b as i64);
+    }
+}
+
+/// Send `cmd` (if any) and collect the reply up to the next prompt
+fn command(droid: &mut Stepper, cmd: Option<&str>) -> Result<(String, Ending), Error> {
+    if let Some(cmd) = cmd {
+        send_line(droid, cmd);
+    }
+    let mut text = String::new();
+    let mut budget = STEP_BUDGET;
+    loop {
+        let before = droid.steps();
+        let y = droid.resume_for(budget)?;
+        budget -= droid.steps() - before;
+        match y {
+            Yield::Output(c) => text.push(c as u8 as char),
+            Yield::NeedsInput => break Ok((text, Ending::Prompt)),
+            Yield::Halted => break Ok((text, Ending::Halted)),
+            Yield::OutOfSteps => break Ok((text, Ending::Stuck)),
+        }
+    }
+}
+
+/// An item is safe if the droid can pick it up and still walk through `door` afterwards. In a
+/// room with no door to try there is no telling, so the item counts as unsafe.
+fn is_safe(droid: &Stepper, item: &str, door: Option<&String>) -> Result<bool, Error> {
+    let door = match door {
+        Some(door) => door,
+        None => return Ok(false),
+    };
+    let mut probe = droid.fork();
+    if command(&mut probe, Some(&format!("take {}", item)))?.1 != Ending::Prompt {
+        return Ok(false);
+    }
+    let (text, ending) = command(&mut probe, Some(door))?;
+    Ok(ending == Ending::Prompt && parse_room(&text).is_some())
+}
+
+/// The droid after exploring the ship, standing at the security checkpoint
+struct Survey {
+    droid: Stepper,
+    held: Vec<String>,
+    dangerous: Vec<String>,
+    floor_dir: String,
+}
+
+/// Walk every room depth-first, picking up every item that passes `is_safe`
+fn explore(mut droid: Stepper) -> Result<Survey, Error> {
+    let (text, _) = command(&mut droid, None)?;
+    let mut room = parse_room(&text).ok_or(Error::Unrecognized(text))?;
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut held = Vec::new();
+    let mut dangerous = Vec::new();
+    // per room: doors not yet taken; the path back to the start
+    let mut unexplored: HashMap<String, Vec<String>> = HashMap::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut to_checkpoint = None;
+    let mut floor_dir = None;
+
+    loop {
+        if seen.insert(room.name.clone()) {
+            for item in &room.items {
+                if is_safe(&droid, item, room.doors.first())? {
+                    command(&mut droid, Some(&format!("take {}", item)))?;
+                    held.push(item.clone());
+                } else {
+                    dangerous.push(item.clone());
+                }
+            }
+            let back = path.last().map(|d| opposite(d));
+            unexplored.insert(
+                room.name.clone(),
+                room.doors
+                    .iter()
+                    .filter(|d| Some(d.as_str()) != back)
+                    .cloned()
+                    .collect(),
+            );
+        }
+
+        let next = unexplored.get_mut(&room.name).and_then(|doors| doors.pop());
+        let dir = match next {
+            Some(d) => d,
+            None => match path.pop() {
+                Some(d) => {
+                    let (text, _) = command(&mut droid, Some(opposite(&d)))?;
+                    room = parse_room(&text).ok_or(Error::Unrecognized(text))?;
+                    continue;
+                }
+                None => break,
+            },
+        };
+
+        let (text, ending) = command(&mut droid, Some(&dir))?;
+        if ending != Ending::Prompt {
+            return Err(Error::Unrecognized(text));
+        }
+        let next_room = parse_room(&text).ok_or(Error::Unrecognized(text.clone()))?;
+        if next_room.name == CHECKPOINT && room.name == CHECKPOINT {
+            // bounced off the pressure-sensitive floor
+            floor_dir = Some(dir);
+            to_checkpoint = Some(path.clone());
+        } else {
+            path.push(dir);
+            room = next_room;
+        }
+    }
+
+    for dir in to_checkpoint.ok_or(Error::NoCheckpoint)? {
+        command(&mut droid, Some(&dir))?;
+    }
+    Ok(Survey {
+        droid,
+        held,
+        dangerous,
+        floor_dir: floor_dir.ok_or(Error::NoCheckpoint)?,
+    })
+}
+
+/// Try every subset of the held items on the floor, stepping through them in Gray-code order so
+/// that after the first attempt each one only takes or drops a single item
+fn crack(survey: &mut Survey) -> Result<String, Error> {
+    if survey.held.len() > MAX_HELD {
+        return Err(Error::TooManyItems(survey.held.len()));
+    }
+    let droid = &mut survey.droid;
+    let mut carrying = (1u32 << survey.held.len()) - 1;
+    for i in 0..(1u32 << survey.held.len()) {
+        let wanted = i ^ (i >> 1);
+        for (bit, item) in survey.held.iter().enumerate() {
+            let mask = 1 << bit;
+            if (wanted ^ carrying) & mask != 0 {
+                let verb = if wanted & mask != 0 { "take" } else { "drop" };
+                command(droid, Some(&format!("{} {}", verb, item)))?;
+            }
+        }
+        carrying = wanted;
+
+        let (text, _) = command(droid, Some(&survey.floor_dir))?;
+        if !text.contains("Alert!") {
+            return text
+                .split_whitespace()
+                .find(|w| w.chars().all(|c| c.is_ascii_digit()))
+                .map(str::to_string)
+                .ok_or(Error::Unrecognized(text.clone()));
+        }
+    }
+    Err(Error::NoPassword)
+}
+
+fn boot(filename: &str) -> Result<Stepper, Error> {
+    let mut data = read_comma_file(filename)?;
+    data.extend(vec![0; 4096]);
+    Ok(Stepper::new(build_machine(data)))
+}
+
+/// Explore the ship and get past the security checkpoint on our own
+fn solve(droid: Stepper) -> Result<String, Error> {
+    let mut survey = explore(droid)?;
+    let password = crack(&mut survey)?;
+    Ok(format!(
+        "{} (dangerous: {})",
+        password,
+        survey.dangerous.join(", ")
+    ))
+}
+
+pub fn run() -> Result<String, Error> {
+    solve(boot("input/day25.txt")?)
+}
+
+/// Relay lines between the console and the droid
+pub fn play() -> Result<(), Error> {
+    let mut droid = boot("input/day25.txt")?;
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        match droid.resume()? {
+            Yield::Output(c) => print!("{}", c as u8 as char),
+            Yield::NeedsInput => {
+                std::io::stdout().flush()?;
+                match lines.next() {
+                    Some(line) => send_line(&mut droid, line?.trim_end()),
+                    None => return Ok(()),
+                }
+            }
+            Yield::Halted | Yield::OutOfSteps => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::day2::compiler::{compile_file, STACK_SIZE};
+
+    /// A small ship in the puzzle's format, compiled from `input/day25-fixture.ic`
+    const FIXTURE: &str = "input/day25-fixture.txt";
+
+    const HULL_BREACH: &str = concat!(
+        "\n\n\n== Hull Breach ==\n",
+        "You got in through a hole in the floor here. To keep your ship from also freezing, ",
+        "the hole has been sealed.\n\n",
+        "Doors here lead:\n- north\n- east\n- west\n\n",
+        "Items here:\n- mug\n\n",
+        "Command?\n",
+    );
+
+    #[test]
+    fn room() {
+        assert_eq!(
+            parse_room(HULL_BREACH),
+            Some(Room {
+                name: "Hull Breach".to_string(),
+                doors: vec!["north".to_string(), "east".to_string(), "west".to_string()],
+                items: vec!["mug".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn ejected() {
+        let text = concat!(
+            "\n\n\n== Pressure-Sensitive Floor ==\n",
+            "Analyzing...\n\nDoors here lead:\n- west\n\n",
+            "A loud, robotic voice says \"Alert! Droids on this ship are heavier than the ",
+            "detected value!\" and you are ejected back to the checkpoint.\n",
+            "\n\n\n== Security Checkpoint ==\n",
+            "In the next room, a pressure-sensitive floor will verify your identity.\n\n",
+            "Doors here lead:\n- north\n- east\n\n",
+            "Command?\n",
+        );
+        let room = parse_room(text).unwrap();
+        assert_eq!(room.name, CHECKPOINT);
+        assert_eq!(room.doors, vec!["north".to_string(), "east".to_string()]);
+        assert!(room.items.is_empty());
+    }
+
+    #[test]
+    fn fixture() {
+        // the fixture is the stand-in ship compiled, without its stack
+        let image = compile_file("input/day25-fixture.ic").unwrap();
+        let code = &image[..image.len() - STACK_SIZE];
+        assert_eq!(read_comma_file(FIXTURE).unwrap(), code);
+    }
+
+    #[test]
+    fn password() {
+        assert_eq!(
+            solve(boot(FIXTURE).unwrap()).unwrap(),
+            "134227456 (dangerous: infinite loop, escape pod, giant electromagnet)"
+        );
+    }
+
+    #[test]
+    fn survey() {
+        let mut survey = explore(boot(FIXTURE).unwrap()).unwrap();
+        assert_eq!(survey.held, vec!["ornament", "coin", "tambourine", "mug"]);
+        assert_eq!(
+            survey.dangerous,
+            vec!["infinite loop", "escape pod", "giant electromagnet"]
+        );
+        assert_eq!(survey.floor_dir, "north");
+
+        // the floor wants the ornament; without it every subset gets tried and fails
+        let (text, _) = command(&mut survey.droid, Some("drop ornament")).unwrap();
+        assert!(text.contains("You drop the ornament."));
+        survey.held.remove(0);
+        assert!(matches!(crack(&mut survey), Err(Error::NoPassword)));
+
+        survey.held = (0..=MAX_HELD).map(|i| i.to_string()).collect();
+        assert!(matches!(
+            crack(&mut survey),
+            Err(Error::TooManyItems(n)) if n == MAX_HELD + 1
+        ));
+    }
+
+    #[test]
+    fn no_doors() {
+        let droid = boot(FIXTURE).unwrap();
+        assert!(!is_safe(&droid, "ornament", None).unwrap());
+    }
+}
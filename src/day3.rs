@@ -2,6 +2,9 @@ use std::convert::TryFrom;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
+use crate::grid::Point;
+use crate::image::{self, Palette};
+
 type RunningLine = (u64, Line);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -260,6 +263,17 @@ fn walk(seq: &[Direction]) -> Vec<Line> {
     seq.iter().map(|m| cursor.mov(m)).collect()
 }
 
+/// Every corner of a wire, starting at the central port
+fn wire_path(seq: &[Direction]) -> Vec<Point> {
+    let mut cursor = Cursor::new();
+    let mut path = vec![(0, 0)];
+    for m in seq {
+        cursor.mov(m);
+        path.push((cursor.0, cursor.1));
+    }
+    path
+}
+
 fn measured_walk(seq: &[Direction]) -> Vec<RunningLine> {
     let mut cursor = Cursor::new();
     let mut running_dist: u64 = 0;
@@ -305,6 +319,23 @@ pub fn run() -> Result<String, DirErr> {
     let dir_lists = read_directions("input/day3.txt")?;
     let (line1_h, line1_v) = gather_segments(walk(&dir_lists[0]).as_slice());
     let (line2_h, line2_v) = gather_segments(walk(&dir_lists[1]).as_slice());
+    let palette = Palette::from_env()?;
+    image::export_svg(
+        3,
+        &image::paths_svg(
+            &[
+                (
+                    palette.color("wire 1", [255, 64, 64]),
+                    wire_path(&dir_lists[0]),
+                ),
+                (
+                    palette.color("wire 2", [64, 160, 255]),
+                    wire_path(&dir_lists[1]),
+                ),
+            ],
+            true,
+        ),
+    )?;
 
     Ok(format!(
         "{}",
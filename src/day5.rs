@@ -15,6 +15,7 @@ pub fn build_machine(mem: Vec<i64>) -> IntCodeMachine {
     m.reg_opcode(op::Eq::code(), op::Eq::new);
     m.reg_opcode(op::Lt::code(), op::Lt::new);
     m.reg_param_mode(1, immediate::load, immediate::store);
+    m.set_isa("day5");
     m
 }
 
@@ -46,7 +47,7 @@ pub mod op {
     use super::read;
     use crate::day2::op::OpCode;
     use crate::day2::param::{decompose_param, ParamReg};
-    use crate::day2::{Error, LoadPtr, StorePtr};
+    use crate::day2::{Error, LoadPtr, Memory, StorePtr};
 
     use std::sync::mpsc::{Receiver, Sender};
 
@@ -58,19 +59,23 @@ pub mod op {
     pub struct Input(StorePtr);
     impl OpCode for Input {
         fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-            let ps = decompose_param(param, Input::width() as usize);
+            let ps = decompose_param(param, Input::width() as usize)?;
             Ok(Box::new(Input(reg.get(ps[0])?.store)) as Box<dyn OpCode>)
         }
 
         fn execute(
             &self,
             ip: isize,
-            mem: &mut [i64],
-            _: &Option<Receiver<i64>>,
+            mem: &mut Memory,
+            inp: &Option<Receiver<i64>>,
             _: &mut Option<Sender<i64>>,
             rel_base: &mut isize,
         ) -> Result<isize, Error> {
-            let result = read::<i64>("INPUT: ")?;
+            // a wired input takes the place of the terminal
+            let result = match inp {
+                Some(rx) => rx.recv()?,
+                None => read::<i64>("INPUT: ")?,
+            };
             debug!("STDIN {}", result);
             self.0(ip + 1, mem, result, *rel_base)?;
             Ok(Input::width() as isize)
@@ -94,14 +99,14 @@ pub mod op {
     pub struct Output(LoadPtr);
     impl OpCode for Output {
         fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-            let ps = decompose_param(param, Output::width());
+            let ps = decompose_param(param, Output::width())?;
             Ok(Box::new(Output(reg.get(ps[0])?.load)) as Box<dyn OpCode>)
         }
 
         fn execute(
             &self,
             ip: isize,
-            mem: &mut [i64],
+            mem: &mut Memory,
             _: &Option<Receiver<i64>>,
             _: &mut Option<Sender<i64>>,
             rel_base: &mut isize,
@@ -131,14 +136,14 @@ pub mod op {
         pub struct Jnz(pub LoadPtr, pub LoadPtr);
         impl OpCode for Jnz {
             fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-                let ps = decompose_param(param, Jnz::width());
+                let ps = decompose_param(param, Jnz::width())?;
                 Ok(Box::new(Jnz(reg.get(ps[0])?.load, reg.get(ps[1])?.load)) as Box<dyn OpCode>)
             }
 
             fn execute(
                 &self,
                 ip: isize,
-                mem: &mut [i64],
+                mem: &mut Memory,
                 _: &Option<Receiver<i64>>,
                 _: &mut Option<Sender<i64>>,
                 rel_base: &mut isize,
@@ -147,7 +152,7 @@ pub mod op {
                 if value != 0 {
                     let dest = self.1(ip + 2, mem, *rel_base)?;
                     debug!("JNZ {} != 0 -> {}", value, dest);
-                    Ok(dest as isize - ip)
+                    (dest as isize).checked_sub(ip).ok_or(Error::Overflow)
                 } else {
                     debug!("JNZ {} == 0", value);
                     Ok(Jnz::width() as isize)
@@ -190,7 +195,7 @@ pub mod op {
                 let (tx, rx) = channel();
 
                 // do not jump, ip = 3
-                let mut mem = vec![1105, 0, 0];
+                let mut mem = Memory::from(vec![1105, 0, 0]);
                 let r = op.execute(0, &mut mem, &Some(rx), &mut Some(tx), &mut 0);
                 assert_eq!(r, Ok(3));
             }
@@ -200,7 +205,7 @@ pub mod op {
                 let op = Jnz(immediate::load, immediate::load);
                 let (tx, rx) = channel();
                 // jump, ip = 0
-                let mut mem = vec![1105, 1, 0];
+                let mut mem = Memory::from(vec![1105, 1, 0]);
                 let r = op.execute(0, &mut mem, &Some(rx), &mut Some(tx), &mut 0);
                 assert_eq!(r, Ok(0));
             }
@@ -210,7 +215,7 @@ pub mod op {
                 let op = Jnz(immediate::load, immediate::load);
                 let (tx, rx) = channel();
                 // jump backwards, ip = 0
-                let mut mem = vec![0, 0, 1105, 1, 0];
+                let mut mem = Memory::from(vec![0, 0, 1105, 1, 0]);
                 let r = op.execute(2, &mut mem, &Some(rx), &mut Some(tx), &mut 0);
                 assert_eq!(r, Ok(-2));
             }
@@ -224,14 +229,14 @@ pub mod op {
         pub struct Jz(LoadPtr, LoadPtr);
         impl OpCode for Jz {
             fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-                let ps = decompose_param(param, Jz::width());
+                let ps = decompose_param(param, Jz::width())?;
                 Ok(Box::new(Jz(reg.get(ps[0])?.load, reg.get(ps[1])?.load)) as Box<dyn OpCode>)
             }
 
             fn execute(
                 &self,
                 ip: isize,
-                mem: &mut [i64],
+                mem: &mut Memory,
                 _: &Option<Receiver<i64>>,
                 _: &mut Option<Sender<i64>>,
                 rel_base: &mut isize,
@@ -240,7 +245,7 @@ pub mod op {
                 if value == 0 {
                     let dest = self.1(ip + 2, mem, *rel_base)?;
                     debug!("JZ {} == 0 -> {}", value, dest);
-                    Ok(dest as isize - ip)
+                    (dest as isize).checked_sub(ip).ok_or(Error::Overflow)
                 } else {
                     debug!("JZ {} != 0", value);
                     Ok(Jz::width() as isize)
@@ -268,19 +273,19 @@ pub mod op {
 
                 // jump, ip = 0
                 let (tx, rx) = channel();
-                let mut mem = vec![115, 0, 0];
+                let mut mem = Memory::from(vec![115, 0, 0]);
                 let r = op.execute(0, &mut mem, &Some(rx), &mut Some(tx), &mut 0);
                 assert_eq!(r, Ok(0));
 
                 // do not jump, ip = 3
                 let (tx, rx) = channel();
-                let mut mem = vec![115, 1, 0];
+                let mut mem = Memory::from(vec![115, 1, 0]);
                 let r = op.execute(0, &mut mem, &Some(rx), &mut Some(tx), &mut 0);
                 assert_eq!(r, Ok(3));
 
                 // jump backwards, ip = 0
                 let (tx, rx) = channel();
-                let mut mem = vec![0, 0, 115, 0, 0];
+                let mut mem = Memory::from(vec![0, 0, 115, 0, 0]);
                 let r = op.execute(2, &mut mem, &Some(rx), &mut Some(tx), &mut 0);
                 assert_eq!(r, Ok(-2));
             }
@@ -299,7 +304,7 @@ pub mod op {
         pub struct Lt(LoadPtr, LoadPtr, StorePtr);
         impl OpCode for Lt {
             fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-                let ps = decompose_param(param, Lt::width());
+                let ps = decompose_param(param, Lt::width())?;
                 Ok(Box::new(Lt(
                     reg.get(ps[0])?.load,
                     reg.get(ps[1])?.load,
@@ -310,7 +315,7 @@ pub mod op {
             fn execute(
                 &self,
                 ip: isize,
-                mem: &mut [i64],
+                mem: &mut Memory,
                 _: &Option<Receiver<i64>>,
                 _: &mut Option<Sender<i64>>,
                 rel_base: &mut isize,
@@ -351,22 +356,22 @@ pub mod op {
             #[test]
             fn test_lt() {
                 // true, write 1 to @3
-                let mut mem = vec![7, 4, 5, 3, 1, 2];
+                let mut mem = Memory::from(vec![7, 4, 5, 3, 1, 2]);
                 let (tx, rx) = channel();
                 let lt = Lt(load, load, store);
                 assert!(lt
                     .execute(0, &mut mem, &Some(rx), &mut Some(tx), &mut 0)
                     .is_ok());
-                assert_eq!(mem, vec![7, 4, 5, 1, 1, 2]);
+                assert_eq!(*mem, [7, 4, 5, 1, 1, 2]);
 
                 // false, write 0 to @3
-                let mut mem = vec![7, 5, 4, 3, 1, 2];
+                let mut mem = Memory::from(vec![7, 5, 4, 3, 1, 2]);
                 let (tx, rx) = channel();
                 let lt = Lt(load, load, store);
                 assert!(lt
                     .execute(0, &mut mem, &Some(rx), &mut Some(tx), &mut 0)
                     .is_ok());
-                assert_eq!(mem, vec![7, 5, 4, 0, 1, 2]);
+                assert_eq!(*mem, [7, 5, 4, 0, 1, 2]);
             }
         }
     }
@@ -378,7 +383,7 @@ pub mod op {
         pub struct Eq(LoadPtr, LoadPtr, StorePtr);
         impl OpCode for Eq {
             fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-                let ps = decompose_param(param, Eq::width());
+                let ps = decompose_param(param, Eq::width())?;
                 Ok(Box::new(Eq(
                     reg.get(ps[0])?.load,
                     reg.get(ps[1])?.load,
@@ -389,7 +394,7 @@ pub mod op {
             fn execute(
                 &self,
                 ip: isize,
-                mem: &mut [i64],
+                mem: &mut Memory,
                 _: &Option<Receiver<i64>>,
                 _: &mut Option<Sender<i64>>,
                 rel_base: &mut isize,
@@ -429,31 +434,30 @@ pub mod op {
 
             #[test]
             fn test_eq() {
-                let mut mem = vec![118, 1, 2, 3];
+                let mut mem = Memory::from(vec![118, 1, 2, 3]);
                 let (tx, rx) = channel();
                 let lt = Eq(load, load, store);
                 assert!(lt
                     .execute(0, &mut mem, &Some(rx), &mut Some(tx), &mut 0)
                     .is_ok());
-                assert_eq!(mem, vec![118, 1, 2, 0]);
+                assert_eq!(*mem, [118, 1, 2, 0]);
 
-                let mut mem = vec![118, 1, 1, 3];
+                let mut mem = Memory::from(vec![118, 1, 1, 3]);
                 let (tx, rx) = channel();
                 let lt = Eq(load, load, store);
                 assert!(lt
                     .execute(0, &mut mem, &Some(rx), &mut Some(tx), &mut 0)
                     .is_ok());
-                assert_eq!(mem, vec![118, 1, 1, 1]);
+                assert_eq!(*mem, [118, 1, 1, 1]);
             }
         }
     }
 }
 
 pub mod immediate {
-    use super::Error;
+    use super::{Error, Memory};
 
     pub fn load(ptr: isize, mem: &[i64], _rel_base: isize) -> Result<i64, Error> {
-        assert!(ptr >= 0);
         let value = *mem.get(ptr as usize).ok_or(Error::MemoryError(ptr))?;
         debug!("IMM LD ${}", value);
         Ok(value)
@@ -461,13 +465,12 @@ pub mod immediate {
 
     pub fn store(
         _ptr: isize,
-        _mem: &mut [i64],
+        _mem: &mut Memory,
         _value: i64,
         _rel_base: isize,
     ) -> Result<(), Error> {
-        unreachable!();
-        // *mem.get_mut(ptr).ok_or(Error::MemoryError)? = value;
-        // Ok(())
+        // nothing can be written through an immediate parameter
+        Err(Error::BadParamMode)
     }
 
     #[cfg(test)]
@@ -484,88 +487,15 @@ pub mod immediate {
 
 #[cfg(test)]
 mod test {
-    use std::cell::RefCell;
-
-    use super::op;
     use super::*;
+    use crate::day2::indirect;
     use crate::day2::op::add::Add;
     use crate::day2::op::OpCode;
-    use crate::day2::param::ParamReg;
-    use crate::day2::{indirect, Error};
 
     use mopa::mopafy;
-    use std::sync::mpsc::{Receiver, Sender};
 
     mopafy!(OpCode);
 
-    // the output is stored in the inner value
-    pub struct MockOutput(pub RefCell<Option<i64>>);
-    impl OpCode for MockOutput {
-        fn new(_reg: &ParamReg, _param: i64) -> Result<Box<dyn OpCode>, Error> {
-            Ok(Box::new(MockOutput(RefCell::new(None))))
-        }
-
-        fn execute(
-            &self,
-            ip: isize,
-            mem: &mut [i64],
-            _: &Option<Receiver<i64>>,
-            _: &mut Option<Sender<i64>>,
-            rel_base: &mut isize,
-        ) -> Result<isize, Error> {
-            *self.0.borrow_mut() = Some(indirect::load(ip + 1, mem, *rel_base)?);
-            Ok(MockOutput::width() as isize)
-        }
-
-        fn width() -> usize {
-            2
-        }
-
-        fn code() -> i64 {
-            4
-        }
-    }
-
-    impl std::fmt::Debug for MockOutput {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(f, "FAKE_OUT")
-        }
-    }
-
-    // the param arg to the input is used as the value to be stored
-    pub struct MockInput(i64);
-    impl OpCode for MockInput {
-        fn new(_reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-            Ok(Box::new(MockInput(param)))
-        }
-
-        fn execute(
-            &self,
-            ip: isize,
-            mem: &mut [i64],
-            _: &Option<Receiver<i64>>,
-            _: &mut Option<Sender<i64>>,
-            rel_base: &mut isize,
-        ) -> Result<isize, Error> {
-            indirect::store(ip + 1, mem, self.0, *rel_base)?;
-            Ok(MockInput::width() as isize)
-        }
-
-        fn width() -> usize {
-            2
-        }
-
-        fn code() -> i64 {
-            3
-        }
-    }
-
-    impl std::fmt::Debug for MockInput {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(f, "FAKE_IN")
-        }
-    }
-
     #[test]
     fn imm_mode_decode() {
         let mut m = IntCodeMachine::boot(Vec::new());
@@ -584,126 +514,6 @@ mod test {
         }
     }
 
-    #[test]
-    fn test_run_eq_ind() {
-        let mem = vec![803, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
-        let mut m = IntCodeMachine::boot(mem);
-        m.reg_opcode(MockInput::code(), MockInput::new);
-        m.reg_opcode(MockOutput::code(), MockOutput::new);
-        m.reg_opcode(op::Eq::code(), op::Eq::new);
-        let end_state = m.run();
-        assert_eq!(end_state, Ok(vec![803, 9, 8, 9, 10, 9, 4, 9, 99, 1, 8]));
-    }
-
-    #[test]
-    fn test_run_lt_ind() {
-        let mem = vec![803, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
-        let mut m = IntCodeMachine::boot(mem);
-        m.reg_opcode(MockInput::code(), MockInput::new);
-        m.reg_opcode(MockOutput::code(), MockOutput::new);
-        m.reg_opcode(op::Lt::code(), op::Lt::new);
-        let end_state = m.run();
-        assert_eq!(end_state, Ok(vec![803, 9, 7, 9, 10, 9, 4, 9, 99, 0, 8]));
-    }
-
-    #[test]
-    fn test_run_eq_imm() {
-        let mem = vec![803, 3, 1108, -1, 8, 3, 4, 3, 99];
-        let mut m = IntCodeMachine::boot(mem);
-        m.reg_param_mode(1, immediate::load, immediate::store);
-        m.reg_opcode(MockInput::code(), MockInput::new);
-        m.reg_opcode(MockOutput::code(), MockOutput::new);
-        m.reg_opcode(op::Eq::code(), op::Eq::new);
-        let end_state = m.run();
-        assert_eq!(end_state, Ok(vec![803, 3, 1108, 1, 8, 3, 4, 3, 99]));
-
-        let mem = vec![903, 3, 1108, -1, 8, 3, 4, 3, 99];
-        let mut m = IntCodeMachine::boot(mem);
-        m.reg_param_mode(1, immediate::load, immediate::store);
-        m.reg_opcode(MockInput::code(), MockInput::new);
-        m.reg_opcode(MockOutput::code(), MockOutput::new);
-        m.reg_opcode(op::Eq::code(), op::Eq::new);
-        let end_state = m.run();
-        assert_eq!(end_state, Ok(vec![903, 3, 1108, 0, 8, 3, 4, 3, 99]));
-    }
-
-    #[test]
-    fn test_run_lt_imm() {
-        let mem = vec![803, 3, 1107, -1, 8, 3, 4, 3, 99];
-        let mut m = IntCodeMachine::boot(mem);
-        m.reg_param_mode(1, immediate::load, immediate::store);
-        m.reg_opcode(MockInput::code(), MockInput::new);
-        m.reg_opcode(MockOutput::code(), MockOutput::new);
-        m.reg_opcode(op::Lt::code(), op::Lt::new);
-        let end_state = m.run();
-        assert_eq!(end_state, Ok(vec![803, 3, 1107, 0, 8, 3, 4, 3, 99]));
-
-        let mem = vec![303, 3, 1107, -1, 8, 3, 4, 3, 99];
-        let mut m = IntCodeMachine::boot(mem);
-        m.reg_param_mode(1, immediate::load, immediate::store);
-        m.reg_opcode(MockInput::code(), MockInput::new);
-        m.reg_opcode(MockOutput::code(), MockOutput::new);
-        m.reg_opcode(op::Lt::code(), op::Lt::new);
-        let end_state = m.run();
-        assert_eq!(end_state, Ok(vec![303, 3, 1107, 1, 8, 3, 4, 3, 99]));
-    }
-
-    #[test]
-    fn test_jmp_int() {
-        let mem = vec![503, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
-        let mut m = IntCodeMachine::boot(mem);
-        m.reg_param_mode(1, immediate::load, immediate::store);
-        m.reg_opcode(MockInput::code(), MockInput::new);
-        m.reg_opcode(MockOutput::code(), MockOutput::new);
-        m.reg_opcode(op::Jz::code(), op::Jz::new);
-        let end_state = m.run();
-        assert_eq!(
-            end_state,
-            Ok(vec![
-                503, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, 5, 1, 1, 9
-            ])
-        );
-
-        let mem = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
-        let mut m = IntCodeMachine::boot(mem);
-        m.reg_param_mode(1, immediate::load, immediate::store);
-        m.reg_opcode(MockInput::code(), MockInput::new);
-        m.reg_opcode(MockOutput::code(), MockOutput::new);
-        m.reg_opcode(op::Jz::code(), op::Jz::new);
-        let end_state = m.run();
-        assert_eq!(
-            end_state,
-            Ok(vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, 0, 0, 1, 9])
-        );
-    }
-
-    #[test]
-    fn test_jmp_imm() {
-        let mem = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
-        let mut m = IntCodeMachine::boot(mem);
-        m.reg_param_mode(1, immediate::load, immediate::store);
-        m.reg_opcode(MockInput::code(), MockInput::new);
-        m.reg_opcode(MockOutput::code(), MockOutput::new);
-        m.reg_opcode(op::Jnz::code(), op::Jnz::new);
-        let end_state = m.run();
-        assert_eq!(
-            end_state,
-            Ok(vec![3, 3, 1105, 0, 9, 1101, 0, 0, 12, 4, 12, 99, 0])
-        );
-
-        let mem = vec![103, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
-        let mut m = IntCodeMachine::boot(mem);
-        m.reg_param_mode(1, immediate::load, immediate::store);
-        m.reg_opcode(MockInput::code(), MockInput::new);
-        m.reg_opcode(MockOutput::code(), MockOutput::new);
-        m.reg_opcode(op::Jnz::code(), op::Jnz::new);
-        let end_state = m.run();
-        assert_eq!(
-            end_state,
-            Ok(vec![103, 3, 1105, 1, 9, 1101, 0, 0, 12, 4, 12, 99, 1])
-        );
-    }
-
     fn print_type_of<T>(_: &T) -> String {
         format!("{}", std::any::type_name::<T>())
     }
@@ -1,10 +1,14 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
+use crate::search;
+
 #[derive(Debug)]
 pub enum Error {
     BadSplit,
+    /// YOU and SAN do not orbit anything in common, or one directly orbits the other
+    NoRoute,
     IoError(std::io::Error),
 }
 
@@ -34,106 +38,32 @@ fn collect_pairs(pairs: &[(String, String)]) -> HashMap<String, Vec<String>> {
     m
 }
 
-fn count_depth(mut map: HashMap<String, Vec<String>>) -> usize {
-    let mut stack = vec![String::from("COM")];
-    let mut total = 0;
-    while !stack.is_empty() {
-        /*
-        println!(
-            "{}",
-            stack
-                .iter()
-                .fold(String::new(), |acc, s| format!("{} {}", acc, s))
-        );
-        */
-        if let Some(children) = map.get(stack.last().unwrap()) {
-            stack.push(children[0].clone());
-            continue;
-        }
-        total += stack.len() - 1;
-        let leaf = stack.pop().unwrap();
-        if stack.is_empty() {
-            break;
-        }
-        let idx = match map.get(stack.last().unwrap()) {
-            Some(l) => l.iter().position(|s| *s == leaf).unwrap(),
-            None => {
-                break;
-            }
-        };
-        map.get_mut(stack.last().unwrap()).unwrap().remove(idx);
-        if map.get(stack.last().unwrap()).unwrap().is_empty() {
-            map.remove(stack.last().unwrap());
-        }
-    }
-    total
+/// Total direct and indirect orbits: every object's depth below COM
+fn count_depth(map: &HashMap<String, Vec<String>>) -> usize {
+    search::bfs(String::from("COM"), |n| {
+        map.get(n).cloned().unwrap_or_default()
+    })
+    .iter()
+    .map(|(_, d)| d)
+    .sum()
 }
 
-fn seek_path(map: &HashMap<String, Vec<String>>, start: &str, finish: &str) -> Option<Vec<String>> {
-    let mut stack = vec![String::from(start)];
-    let mut map = map.clone();
-    loop {
-        /*
-        println!(
-            "{}",
-            stack
-                .iter()
-                .fold(String::new(), |acc, s| format!("{} {}", acc, s))
-        );
-        */
-        // if we're not at a leaf
-        if let Some(children) = map.get(stack.last().unwrap()) {
-            // if this node holds our destination, we can bail immediately
-            if children.iter().position(|s| s == finish).is_some() {
-                stack.push(String::from(finish));
-                return Some(stack);
-            }
-
-            // keep looking
-            stack.push(children[0].clone());
-            continue;
-        }
-
-        let leaf = stack.pop().unwrap();
-        if stack.is_empty() {
-            return None;
-        }
-
-        let idx = match map.get(stack.last().unwrap()) {
-            Some(l) => l.iter().position(|s| *s == leaf).unwrap(),
-            None => {
-                return None;
-            }
-        };
-        map.get_mut(stack.last().unwrap()).unwrap().remove(idx);
-        if map.get(stack.last().unwrap()).unwrap().is_empty() {
-            map.remove(stack.last().unwrap());
-        }
-    }
-}
-
-fn find_root(left: &mut VecDeque<String>, right: &mut VecDeque<String>) {
-    let mut last = String::new();
-    while left.as_slices().0.first() == right.as_slices().0.first() {
-        last = left.pop_front().unwrap();
-        right.pop_front();
-    }
-    left.push_front(last.to_string());
-    right.push_front(last.to_string());
+/// Orbital transfers to get from what YOU orbit to what SAN orbits
+fn transfers(map: &HashMap<String, Vec<String>>) -> Result<usize, Error> {
+    let you = String::from("YOU");
+    let path = search::bfs_to(you, &String::from("SAN"), search::tree_neighbors(map))
+        .ok_or(Error::NoRoute)?;
+    // the route counts YOU, SAN and the body SAN orbits, none of which are transfers
+    path.len().checked_sub(3).ok_or(Error::NoRoute)
 }
 
 pub fn run() -> Result<String, Error> {
     let pairs = read_file("input/day6.txt")?;
     let pairs = collect_pairs(&pairs);
 
-    let you = seek_path(&pairs, "COM", "YOU");
-    let santa = seek_path(&pairs, "COM", "SAN");
-    let mut you = VecDeque::from(you.unwrap());
-    let mut santa = VecDeque::from(santa.unwrap());
-    find_root(&mut you, &mut santa);
-    let num_xfer = you.len() - 2 + santa.len() - 2;
+    let num_xfer = transfers(&pairs)?;
 
-    Ok(format!("{} {}", count_depth(pairs), num_xfer))
+    Ok(format!("{} {}", count_depth(&pairs), num_xfer))
 }
 
 #[cfg(test)]
@@ -151,7 +81,7 @@ mod test {
             (p[0].to_string(), p[1].to_string())
         })
         .collect::<Vec<(String, String)>>();
-        assert_eq!(count_depth(collect_pairs(&data)), 42);
+        assert_eq!(count_depth(&collect_pairs(&data)), 42);
     }
 
     #[test]
@@ -167,8 +97,12 @@ mod test {
         })
         .collect::<Vec<(String, String)>>();
         let map = collect_pairs(&map);
-        println!("seeking you");
-        let you = seek_path(&map, "COM", "YOU");
+        let seek_path = |start: &str, finish: &str| {
+            search::bfs_to(start.to_string(), &finish.to_string(), |n| {
+                map.get(n).cloned().unwrap_or_default()
+            })
+        };
+        let you = seek_path("COM", "YOU");
         assert_eq!(
             you,
             Some(vec![
@@ -182,8 +116,7 @@ mod test {
                 "YOU".to_string(),
             ])
         );
-        println!("seeking santa");
-        let santa = seek_path(&map, "COM", "SAN");
+        let santa = seek_path("COM", "SAN");
         assert_eq!(
             santa,
             Some(vec![
@@ -195,9 +128,18 @@ mod test {
                 "SAN".to_string()
             ])
         );
-        let mut you = VecDeque::from(you.unwrap());
-        let mut santa = VecDeque::from(santa.unwrap());
-        find_root(&mut you, &mut santa);
-        assert_eq!(you.len() - 2 + santa.len() - 2, 4);
+        assert_eq!(transfers(&map).unwrap(), 4);
+    }
+
+    #[test]
+    fn direct_orbit() {
+        let pair = |a: &str, b: &str| (a.to_string(), b.to_string());
+        let map = collect_pairs(&[pair("COM", "SAN"), pair("SAN", "YOU")]);
+        assert!(matches!(transfers(&map), Err(Error::NoRoute)));
+        let map = collect_pairs(&[pair("COM", "YOU"), pair("YOU", "SAN")]);
+        assert!(matches!(transfers(&map), Err(Error::NoRoute)));
+        // already orbiting the same body
+        let map = collect_pairs(&[pair("COM", "YOU"), pair("COM", "SAN")]);
+        assert_eq!(transfers(&map).unwrap(), 0);
     }
 }
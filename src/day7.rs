@@ -16,54 +16,15 @@ pub fn build_machine(mem: Vec<i64>) -> IntCodeMachine {
     m.reg_opcode(day5::op::Eq::code(), day5::op::Eq::new);
     m.reg_opcode(day5::op::Lt::code(), day5::op::Lt::new);
     m.reg_param_mode(1, immediate::load, immediate::store);
+    m.set_isa("day7");
     m
 }
 
 pub fn run() -> Result<String, Error> {
     let data = read_comma_file("input/day7.txt")?;
 
-    let result = permute::permutations_of(&[0, 1, 2, 3, 4])
-        .map(|seq| {
-            let mut cluster = Cluster::build(5, &data.clone());
-            cluster.start().unwrap();
-            let seq: Vec<i64> = seq
-                .enumerate()
-                .map(|(i, v)| {
-                    cluster.input(i, *v).unwrap();
-                    *v
-                })
-                .collect();
-
-            // then input value
-            cluster.input(0, 0).unwrap();
-
-            cluster.finish().unwrap();
-            (seq, cluster.read_output().unwrap())
-        })
-        .max_by_key(|r| r.1)
-        .unwrap();
-
-    let result_2 = permute::permutations_of(&[9, 8, 7, 6, 5])
-        .map(|seq| {
-            let mut cluster = Cluster::build(5, &data);
-            cluster.start().unwrap();
-            let seq: Vec<i64> = seq
-                .enumerate()
-                .map(|(i, v)| {
-                    cluster.input(i, *v).unwrap();
-                    *v
-                })
-                .collect();
-
-            let mut next_input = 0;
-            while let Ok(_) = cluster.input(0, next_input) {
-                next_input = cluster.read_output().unwrap();
-            }
-            cluster.finish().unwrap();
-            (seq, next_input)
-        })
-        .max_by_key(|r| r.1)
-        .unwrap();
+    let result = best(&[0, 1, 2, 3, 4], |seq| amplify(&data, seq))?;
+    let result_2 = best(&[9, 8, 7, 6, 5], |seq| feedback(&data, seq))?;
 
     Ok(format!(
         "{:?}, {} | {:?} {}",
@@ -71,9 +32,63 @@ pub fn run() -> Result<String, Error> {
     ))
 }
 
-pub struct Cluster {
+/// The phase sequence that gives the strongest signal, and that signal
+fn best<F>(phases: &[i64], signal: F) -> Result<(Vec<i64>, i64), Error>
+where
+    F: Fn(&[i64]) -> Result<i64, Error>,
+{
+    let results = permute::permutations_of(phases)
+        .map(|seq| {
+            let seq: Vec<i64> = seq.copied().collect();
+            let v = signal(&seq)?;
+            Ok((seq, v))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    results
+        .into_iter()
+        .max_by_key(|r| r.1)
+        .ok_or(Error::NoPhases)
+}
+
+/// Run the amplifiers once in series, starting from a signal of 0
+fn amplify(data: &[i64], seq: &[i64]) -> Result<i64, Error> {
+    let mut cluster = Cluster::build(5, data);
+    cluster.start()?;
+    for (i, v) in seq.iter().enumerate() {
+        cluster.input(i, *v)?;
+    }
+
+    // then input value
+    cluster.input(0, 0)?;
+
+    cluster.finish()?;
+    cluster.read_output()
+}
+
+/// Feed the last amplifier's output back into the first until they halt
+fn feedback(data: &[i64], seq: &[i64]) -> Result<i64, Error> {
+    let mut cluster = Cluster::build(5, data);
+    cluster.start()?;
+    for (i, v) in seq.iter().enumerate() {
+        cluster.input(i, *v)?;
+    }
+
+    let mut next_input = 0;
+    while cluster.input(0, next_input).is_ok() {
+        // the first machine may still be shutting down after its last round
+        match cluster.read_output() {
+            Ok(v) => next_input = v,
+            Err(Error::Terminated) => break,
+            Err(e) => return Err(e),
+        }
+    }
+    cluster.finish()?;
+    Ok(next_input)
+}
+
+pub struct Cluster<M: Wired = IntCodeMachine> {
     /// the IntCode machines, each with its output wired to the input of the next
-    machines: Option<Vec<IntCodeMachine>>,
+    machines: Option<Vec<M>>,
     /// the final output
     output: Receiver<i64>,
     /// an input for each machine
@@ -83,6 +98,12 @@ pub struct Cluster {
 }
 
 impl Cluster {
+    pub fn build(num: usize, mem: &[i64]) -> Self {
+        Self::build_with(num, mem, build_module)
+    }
+}
+
+impl<M: Wired> Cluster<M> {
     pub fn input(&mut self, id: usize, value: i64) -> Result<(), Error> {
         Ok(self
             .inputs
@@ -91,18 +112,20 @@ impl Cluster {
             .send(value)?)
     }
 
-    pub fn read_output(&mut self) -> Option<i64> {
-        self.output
-            .recv_timeout(std::time::Duration::from_secs(1))
-            .ok()
+    /// The next value out of the last machine, waiting for it if need be. Fails with
+    /// `Terminated` once that machine has halted and every value is read. Blocks for good if the
+    /// machines are waiting on input only the caller can give.
+    pub fn read_output(&mut self) -> Result<i64, Error> {
+        self.output.recv().map_err(|_| Error::Terminated)
     }
 
-    pub fn build(num: usize, mem: &[i64]) -> Self {
-        let mut machines = vec![build_module(mem.to_vec())];
+    /// Build a cluster of `num` machines, each booted from `mem` by `boot`
+    pub fn build_with(num: usize, mem: &[i64], boot: fn(Vec<i64>) -> M) -> Self {
+        let mut machines = vec![boot(mem.to_vec())];
         let mut terminals = Vec::new();
 
         for i in 1..num {
-            let mut next = build_module(mem.to_vec());
+            let mut next = boot(mem.to_vec());
             let tx = next.wire_input();
             machines[i - 1].wire_output(tx.clone());
             machines.push(next);
@@ -121,7 +144,7 @@ impl Cluster {
         }
     }
 
-    pub fn start(&mut self) -> Result<(), ()> {
+    pub fn start(&mut self) -> Result<(), Error> {
         if let Some(m) = self.machines.take() {
             self.t_handles = Some(
                 m.into_iter()
@@ -130,15 +153,18 @@ impl Cluster {
             );
             Ok(())
         } else {
-            Err(())
+            Err(Error::AlreadyRunning)
         }
     }
 
+    /// Wait for every machine to halt and return their memories. Closes the inputs first, so a
+    /// machine still waiting on one fails instead of blocking forever.
     pub fn finish(&mut self) -> Result<Vec<Vec<i64>>, Error> {
         if let Some(handles) = self.t_handles.take() {
+            self.inputs.clear();
             handles
                 .into_iter()
-                .map(move |h| h.join().unwrap())
+                .map(|h| h.join().map_err(|_| Error::ThreadPanicked)?)
                 .collect()
         } else {
             Err(Error::NotRunning)
@@ -155,27 +181,28 @@ pub(crate) fn build_module(mem: Vec<i64>) -> IntCodeMachine {
     m.reg_opcode(day5::op::Jz::code(), day5::op::Jz::new);
     m.reg_opcode(day5::op::Eq::code(), day5::op::Eq::new);
     m.reg_opcode(day5::op::Lt::code(), day5::op::Lt::new);
+    m.set_isa("day7");
     m
 }
 
 pub mod op {
     use crate::day2::op::OpCode;
     use crate::day2::param::{decompose_param, ParamReg};
-    use crate::day2::{Error, LoadPtr, StorePtr};
+    use crate::day2::{Error, LoadPtr, Memory, StorePtr};
 
     use std::sync::mpsc::{Receiver, Sender};
 
     pub struct WiredInput(StorePtr);
     impl OpCode for WiredInput {
         fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-            let ps = decompose_param(param, WiredInput::width() as usize);
+            let ps = decompose_param(param, WiredInput::width() as usize)?;
             Ok(Box::new(WiredInput(reg.get(ps[0])?.store)))
         }
 
         fn execute(
             &self,
             ip: isize,
-            mem: &mut [i64],
+            mem: &mut Memory,
             inp: &Option<Receiver<i64>>,
             _: &mut Option<Sender<i64>>,
             rel_base: &mut isize,
@@ -205,14 +232,14 @@ pub mod op {
     pub struct WiredOutput(LoadPtr);
     impl OpCode for WiredOutput {
         fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-            let ps = decompose_param(param, WiredOutput::width() as usize);
+            let ps = decompose_param(param, WiredOutput::width() as usize)?;
             Ok(Box::new(WiredOutput(reg.get(ps[0])?.load)))
         }
 
         fn execute(
             &self,
             ip: isize,
-            mem: &mut [i64],
+            mem: &mut Memory,
             _: &Option<Receiver<i64>>,
             out: &mut Option<Sender<i64>>,
             rel_base: &mut isize,
@@ -367,8 +394,12 @@ mod day7_test {
         cluster.input(4, 5).unwrap();
 
         let mut next_input = 0;
-        while let Ok(_) = cluster.input(0, next_input) {
-            next_input = cluster.read_output().unwrap();
+        while cluster.input(0, next_input).is_ok() {
+            match cluster.read_output() {
+                Ok(v) => next_input = v,
+                Err(Error::Terminated) => break,
+                Err(e) => panic!("{:?}", e),
+            }
         }
         cluster.finish().unwrap();
 
@@ -392,11 +423,54 @@ mod day7_test {
         cluster.input(4, 6).unwrap();
 
         let mut next_input = 0;
-        while let Ok(_) = cluster.input(0, next_input) {
-            next_input = cluster.read_output().unwrap();
+        while cluster.input(0, next_input).is_ok() {
+            match cluster.read_output() {
+                Ok(v) => next_input = v,
+                Err(Error::Terminated) => break,
+                Err(e) => panic!("{:?}", e),
+            }
         }
         cluster.finish().unwrap();
 
         assert_eq!(18216, next_input);
     }
+
+    #[test]
+    fn faults() {
+        // the second machine reads its phase, then hits an unknown opcode
+        let mut cluster = Cluster::build_with(2, &[3, 3, 98, 0], ThreadedMachine::boot);
+        cluster.start().unwrap();
+        assert_eq!(cluster.start(), Err(Error::AlreadyRunning));
+        cluster.input(0, 1).unwrap();
+        cluster.input(1, 1).unwrap();
+        assert_eq!(cluster.finish(), Err(Error::BadOpcode(98)));
+        assert_eq!(cluster.read_output(), Err(Error::Terminated));
+        assert_eq!(cluster.finish(), Err(Error::NotRunning));
+    }
+
+    #[test]
+    fn test_fb_threaded() {
+        let data = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+
+        let mut cluster = Cluster::build_with(5, &data, ThreadedMachine::boot);
+        cluster.start().unwrap();
+        for (i, phase) in [9, 8, 7, 6, 5].iter().enumerate() {
+            cluster.input(i, *phase).unwrap();
+        }
+
+        let mut next_input = 0;
+        while cluster.input(0, next_input).is_ok() {
+            match cluster.read_output() {
+                Ok(v) => next_input = v,
+                Err(Error::Terminated) => break,
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+        cluster.finish().unwrap();
+
+        assert_eq!(139629729, next_input);
+    }
 }
@@ -2,6 +2,9 @@ use std::error::Error;
 use std::fs::File;
 use std::io::{BufReader, Read};
 
+use crate::image::{self, Image, Palette};
+use crate::ocr;
+
 const HEIGHT: usize = 6;
 const WIDTH: usize = 25;
 
@@ -42,31 +45,54 @@ pub fn run() -> Result<String, Box<dyn Error>> {
         .filter(|b| **b != 0)
         .partition(|b| **b == 1);
 
-    let rendered: Vec<u8> = layers
-        .iter()
-        .fold(vec![2; HEIGHT * WIDTH], |acc, layer| {
-            acc.iter()
-                .zip(layer.iter())
-                .map(|(top, bot)| if *top == 2 { *bot } else { *top })
-                .collect()
-        })
+    let decoded: Vec<u8> = layers.iter().fold(vec![2; HEIGHT * WIDTH], |acc, layer| {
+        acc.iter()
+            .zip(layer.iter())
+            .map(|(top, bot)| if *top == 2 { *bot } else { *top })
+            .collect()
+    });
+
+    let palette = Palette::from_env()?;
+    let mut picture = Image::new(WIDTH, HEIGHT, image::BLACK);
+    for (i, pixel) in decoded.iter().enumerate() {
+        let color = match pixel {
+            0 => palette.color("black", image::BLACK),
+            1 => palette.color("white", image::WHITE),
+            _ => palette.color("transparent", [128, 128, 128]),
+        };
+        picture.set(i % WIDTH, i / WIDTH, color);
+    }
+    image::export(8, &picture)?;
+
+    let rendered: Vec<u8> = decoded
         .iter()
         .map(|b| if *b == 0 { b' ' } else { b'.' })
         .collect();
 
     // dbg!(&ones, &twos);
+    let image = rendered.iter().cloned().map(char::from).enumerate().fold(
+        String::new(),
+        |mut acc, (c, pixel)| {
+            acc = format!("{}{}", acc, pixel);
+            if (c + 1) % WIDTH == 0 {
+                acc = format!("{}\n", acc);
+            }
+            acc
+        },
+    );
     Ok(format!(
-        "{}\n{}",
+        "{}, {}",
         ones.len() * twos.len(),
-        rendered.iter().cloned().map(char::from).enumerate().fold(
-            String::new(),
-            |mut acc, (c, pixel)| {
-                acc = format!("{}{}", acc, pixel);
-                if (c + 1) % WIDTH == 0 {
-                    acc = format!("{}\n", acc);
-                }
-                acc
-            }
-        )
+        ocr::read_text(&image, |pixel| pixel == '.')?
     ))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn answers() {
+        assert_eq!(run().unwrap(), "2080, AURCY");
+    }
+}
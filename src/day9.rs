@@ -5,10 +5,15 @@ use crate::day7::build_machine as _build_machine;
 
 use std::sync::mpsc::channel;
 
+/// BOOST, transpiled ahead of time from `input/day9.txt`
+#[rustfmt::skip]
+pub mod boost;
+
 pub fn build_machine(mem: Vec<i64>) -> IntCodeMachine {
     let mut machine = _build_machine(mem);
     machine.reg_opcode(op::MoveRel::code(), op::MoveRel::new);
     machine.reg_param_mode(2, rel::load, rel::store);
+    machine.set_isa("day9");
     machine
 }
 
@@ -22,8 +27,8 @@ pub fn run() -> Result<String, Error> {
         let mut machine = build_machine(data.clone());
         machine.wire_output(o_tx);
         let i_tx = machine.wire_input();
-        i_tx.send(1).unwrap();
-        let _ = machine.run().unwrap();
+        i_tx.send(1)?;
+        machine.run()?;
         o_rx.recv()?
     };
 
@@ -33,8 +38,8 @@ pub fn run() -> Result<String, Error> {
         let mut machine = build_machine(data.clone());
         machine.wire_output(o_tx);
         let i_tx = machine.wire_input();
-        i_tx.send(2).unwrap();
-        let _ = machine.run().unwrap();
+        i_tx.send(2)?;
+        machine.run()?;
         o_rx.recv()?
     };
 
@@ -42,19 +47,19 @@ pub fn run() -> Result<String, Error> {
 }
 
 pub mod rel {
-    use crate::day2::Error;
+    use crate::day2::{Error, Memory};
 
-    pub fn store(ptr: isize, mem: &mut [i64], value: i64, rel_base: isize) -> Result<(), Error> {
+    pub fn store(ptr: isize, mem: &mut Memory, value: i64, rel_base: isize) -> Result<(), Error> {
         let rel_offset = *mem.get(ptr as usize).ok_or(Error::MemoryError(ptr))?;
-        let iptr = rel_offset as isize + rel_base;
-        *mem.get_mut(iptr as usize).ok_or(Error::MemoryError(iptr))? = value;
-        Ok(())
+        let iptr = (rel_offset as isize)
+            .checked_add(rel_base)
+            .ok_or(Error::Overflow)?;
+        mem.write(iptr, value)
     }
 
     pub fn load(ptr: isize, mem: &[i64], rel_base: isize) -> Result<i64, Error> {
-        assert!(ptr > 0);
         let rel_offset = *mem.get(ptr as usize).ok_or(Error::MemoryError(ptr))? as isize;
-        let iptr = rel_offset + rel_base;
+        let iptr = rel_offset.checked_add(rel_base).ok_or(Error::Overflow)?;
         let value = *mem.get(iptr as usize).ok_or(Error::MemoryError(iptr))?;
         Ok(value)
     }
@@ -70,6 +75,13 @@ pub mod rel {
             assert_eq!(load(1, &mem, 1), Ok(100));
         }
 
+        #[test]
+        fn rel_get_zero() {
+            let mem = vec![1, 7];
+            assert_eq!(load(0, &mem, 0), Ok(7));
+            assert_eq!(load(0, &mem, isize::MAX), Err(Error::Overflow));
+        }
+
         #[test]
         fn rel_get_backwards() {
             let mem = vec![12, 1, 100];
@@ -78,29 +90,29 @@ pub mod rel {
 
         #[test]
         fn rel_store() {
-            let mut mem = [0, 1];
+            let mut mem = Memory::from(vec![0, 1]);
             let r = store(0, &mut mem, -1, 0);
             assert_eq!(r, Ok(()));
-            assert_eq!(mem, [-1, 1]);
+            assert_eq!(*mem, [-1, 1]);
 
-            let mut mem = [0, 1];
+            let mut mem = Memory::from(vec![0, 1]);
             let r = store(1, &mut mem, -1, 0);
             assert_eq!(r, Ok(()));
-            assert_eq!(mem, [0, -1]);
+            assert_eq!(*mem, [0, -1]);
 
-            let mut mem = [0, 1];
+            let mut mem = Memory::from(vec![0, 1]);
             let r = store(0, &mut mem, -1, 1);
             assert_eq!(r, Ok(()));
-            assert_eq!(mem, [0, -1]);
+            assert_eq!(*mem, [0, -1]);
 
-            let mut mem = [0, 1];
+            let mut mem = Memory::from(vec![0, 1]);
             let r = store(1, &mut mem, -1, 1);
             assert_eq!(r, Err(Error::MemoryError(2)));
 
-            let mut mem = [0, 1];
+            let mut mem = Memory::from(vec![0, 1]);
             let r = store(1, &mut mem, -1, -1);
             assert_eq!(r, Ok(()));
-            assert_eq!(mem, [-1, 1]);
+            assert_eq!(*mem, [-1, 1]);
         }
     }
 }
@@ -108,28 +120,28 @@ pub mod rel {
 pub mod op {
     use crate::day2::op::OpCode;
     use crate::day2::param::{decompose_param, ParamReg};
-    use crate::day2::{Error, LoadPtr};
+    use crate::day2::{Error, LoadPtr, Memory};
 
     use std::sync::mpsc::{Receiver, Sender};
 
     pub struct MoveRel(LoadPtr);
     impl OpCode for MoveRel {
         fn new(reg: &ParamReg, param: i64) -> Result<Box<dyn OpCode>, Error> {
-            let ps = decompose_param(param, MoveRel::width() as usize);
+            let ps = decompose_param(param, MoveRel::width() as usize)?;
             Ok(Box::new(MoveRel(reg.get(ps[0])?.load)) as Box<dyn OpCode>)
         }
 
         fn execute(
             &self,
             ip: isize,
-            mem: &mut [i64],
+            mem: &mut Memory,
             _: &Option<Receiver<i64>>,
             _: &mut Option<Sender<i64>>,
             rel_base: &mut isize,
         ) -> Result<isize, Error> {
             let adj = self.0(ip + 1, mem, *rel_base)?;
             //let adj = *mem.get((ip + 1) as usize).ok_or(Error::MemoryError(ip+1))?;
-            let nrel_base = *rel_base + adj as isize;
+            let nrel_base = rel_base.checked_add(adj as isize).ok_or(Error::Overflow)?;
             debug!("MOVREL {} + {} = {}", rel_base, adj, nrel_base);
             *rel_base = nrel_base;
             Ok(MoveRel::width() as isize)
@@ -158,7 +170,7 @@ pub mod op {
 
         #[test]
         fn moverel() {
-            let mut mem = vec![109, 19];
+            let mut mem = Memory::from(vec![109, 19]);
             let mut rel_base = 2000;
             let op = MoveRel(immediate::load);
             assert!(op
@@ -166,7 +178,7 @@ pub mod op {
                 .is_ok());
             assert_eq!(rel_base, 2019);
 
-            let mut mem = vec![109, 1];
+            let mut mem = Memory::from(vec![109, 1]);
             rel_base = 1;
             assert!(op
                 .execute(0, &mut mem, &mut None, &mut None, &mut rel_base)
@@ -183,44 +195,24 @@ mod day9_test {
     use std::sync::mpsc::channel;
 
     #[test]
-    fn quine() {
-        let o_mem = vec![
-            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
-        ];
-        let mut mem = o_mem.clone();
-        let l = mem.len();
-        mem.extend(&vec![0; 1024 - l]);
-        println!("len is {}", mem.len());
-
-        let mut machine = build_machine(mem.clone());
-        let (tx, rx) = channel();
-        machine.wire_output(tx);
-        let _ = machine.run().unwrap();
-        let mut output = Vec::new();
-        while let Ok(v) = rx.recv() {
-            output.push(v);
+    fn transpiled() {
+        let mut data = read_comma_file("input/day9.txt").unwrap();
+        data.extend(&vec![0; 2048]);
+        for mode in 1..=2 {
+            let (o_tx, o_rx) = channel();
+            let (i_tx, i_rx) = channel();
+            i_tx.send(mode).unwrap();
+            let native = boost::boost(data.clone(), i_rx, o_tx).unwrap();
+
+            let (o_tx, o_rx2) = channel();
+            let mut machine = build_machine(data.clone());
+            machine.wire_output(o_tx);
+            machine.wire_input().send(mode).unwrap();
+            assert_eq!(machine.run().unwrap(), native);
+            assert_eq!(
+                o_rx.try_iter().collect::<Vec<i64>>(),
+                o_rx2.try_iter().collect::<Vec<i64>>()
+            );
         }
-        assert_eq!(output, o_mem);
-    }
-
-    #[test]
-    fn big_num() {
-        let mem = vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0];
-        let mut machine = build_machine(mem);
-        let (tx, rx) = channel();
-        machine.wire_output(tx);
-        let _ = machine.run().unwrap();
-        let s = format!("{}", rx.recv().unwrap());
-        assert_eq!(16, s.len());
-    }
-
-    #[test]
-    fn print_big() {
-        let mem = vec![104, 1125899906842624, 99];
-        let mut machine = build_machine(mem);
-        let (tx, rx) = channel();
-        machine.wire_output(tx);
-        let _ = machine.run().unwrap();
-        assert_eq!(1125899906842624, rx.recv().unwrap());
     }
 }
@@ -0,0 +1,1307 @@
+// @generated by `aoc2019 aot` from a 973 word program
+#![allow(unused_mut, unreachable_code, dead_code, clippy::all)]
+
+use std::sync::mpsc::{Receiver, Sender};
+
+use crate::day2::transpile::{add, fallback, load, mul, resume_at, store};
+use crate::day2::Error;
+
+fn is_code(addr: i64) -> bool {
+    matches!(addr, 0..=62 | 65..=972)
+}
+
+pub fn boost(
+    mut mem: Vec<i64>,
+    input: Receiver<i64>,
+    output: Sender<i64>,
+) -> Result<Vec<i64>, Error> {
+    let mut ip: i64 = 0;
+    let mut rel: i64 = 0;
+    loop {
+        match ip {
+            0 => {
+                // 0: [34463338, 34463338, 63]
+                let v = mul(34463338, 34463338)?;
+                store(&mut mem, 63, v)?;
+                // 4: [63, 34463338, 63]
+                let v = (load(&mem, 63)? < 34463338) as i64;
+                store(&mut mem, 63, v)?;
+                // 8: [63, 53]
+                if load(&mem, 63)? != 0 {
+                    ip = 53;
+                    continue;
+                }
+                ip = 11;
+            }
+            11 => {
+                // 11: [3, 0, 1000]
+                let v = add(3, 0)?;
+                store(&mut mem, 1000, v)?;
+                // 15: [988]
+                rel = add(rel, 988)?;
+                // 17: [12]
+                rel = add(rel, load(&mem, add(rel, 12)?)?)?;
+                // 19: [1000]
+                rel = add(rel, load(&mem, 1000)?)?;
+                ip = 21;
+            }
+            21 => {
+                // 21: [6]
+                rel = add(rel, load(&mem, add(rel, 6)?)?)?;
+                ip = 23;
+            }
+            23 => {
+                // 23: [3]
+                rel = add(rel, load(&mem, add(rel, 3)?)?)?;
+                ip = 25;
+            }
+            25 => {
+                // 25: [0]
+                let v = input.recv()?;
+                let d = add(rel, 0)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 25, 203, 27);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                ip = 27;
+            }
+            27 => {
+                // 27: [1000, 1, 63]
+                let v = (load(&mem, 1000)? == 1) as i64;
+                store(&mut mem, 63, v)?;
+                ip = 31;
+            }
+            31 => {
+                // 31: [63, 65]
+                if load(&mem, 63)? != 0 {
+                    ip = 65;
+                    continue;
+                }
+                ip = 34;
+            }
+            34 => {
+                // 34: [1000, 2, 63]
+                let v = (load(&mem, 1000)? == 2) as i64;
+                store(&mut mem, 63, v)?;
+                ip = 38;
+            }
+            38 => {
+                // 38: [63, 904]
+                if load(&mem, 63)? != 0 {
+                    ip = 904;
+                    continue;
+                }
+                ip = 41;
+            }
+            41 => {
+                // 41: [1000, 0, 63]
+                let v = (load(&mem, 1000)? == 0) as i64;
+                store(&mut mem, 63, v)?;
+                ip = 45;
+            }
+            45 => {
+                // 45: [63, 58]
+                if load(&mem, 63)? != 0 {
+                    ip = 58;
+                    continue;
+                }
+                ip = 48;
+            }
+            48 => {
+                // 48: [25]
+                output.send(load(&mem, 25)?)?;
+                // 50: [0]
+                output.send(0)?;
+                // 52: []
+                return Ok(mem);
+            }
+            53 => {
+                // 53: [0]
+                output.send(load(&mem, 0)?)?;
+                // 55: [0]
+                output.send(0)?;
+                // 57: []
+                return Ok(mem);
+            }
+            58 => {
+                // 58: [17]
+                output.send(load(&mem, 17)?)?;
+                // 60: [0]
+                output.send(0)?;
+                // 62: []
+                return Ok(mem);
+            }
+            65 => {
+                // 65: [37, 0, 1005]
+                let v = add(37, 0)?;
+                store(&mut mem, 1005, v)?;
+                // 69: [30, 0, 1013]
+                let v = add(30, 0)?;
+                store(&mut mem, 1013, v)?;
+                // 73: [1, 33, 1019]
+                let v = mul(1, 33)?;
+                store(&mut mem, 1019, v)?;
+                // 77: [1, 25, 1003]
+                let v = mul(1, 25)?;
+                store(&mut mem, 1003, v)?;
+                // 81: [1, 28, 1018]
+                let v = mul(1, 28)?;
+                store(&mut mem, 1018, v)?;
+                // 85: [26, 0, 1006]
+                let v = add(26, 0)?;
+                store(&mut mem, 1006, v)?;
+                // 89: [1, 866, 1029]
+                let v = mul(1, 866)?;
+                store(&mut mem, 1029, v)?;
+                // 93: [760, 0, 1023]
+                let v = add(760, 0)?;
+                store(&mut mem, 1023, v)?;
+                // 97: [39, 1, 1012]
+                let v = mul(39, 1)?;
+                store(&mut mem, 1012, v)?;
+                // 101: [23, 1, 1009]
+                let v = mul(23, 1)?;
+                store(&mut mem, 1009, v)?;
+                // 105: [281, 0, 1026]
+                let v = add(281, 0)?;
+                store(&mut mem, 1026, v)?;
+                // 109: [1, 20, 1011]
+                let v = mul(1, 20)?;
+                store(&mut mem, 1011, v)?;
+                // 113: [1, 34, 1008]
+                let v = mul(1, 34)?;
+                store(&mut mem, 1008, v)?;
+                // 117: [0, 36, 1017]
+                let v = add(0, 36)?;
+                store(&mut mem, 1017, v)?;
+                // 121: [38, 0, 1000]
+                let v = add(38, 0)?;
+                store(&mut mem, 1000, v)?;
+                // 125: [0, 1, 1020]
+                let v = mul(0, 1)?;
+                store(&mut mem, 1020, v)?;
+                // 129: [278, 1, 1027]
+                let v = mul(278, 1)?;
+                store(&mut mem, 1027, v)?;
+                // 133: [21, 0, 1010]
+                let v = add(21, 0)?;
+                store(&mut mem, 1010, v)?;
+                // 137: [875, 1, 1028]
+                let v = mul(875, 1)?;
+                store(&mut mem, 1028, v)?;
+                // 141: [0, 212, 1025]
+                let v = add(0, 212)?;
+                store(&mut mem, 1025, v)?;
+                // 145: [1, 1, 1021]
+                let v = mul(1, 1)?;
+                store(&mut mem, 1021, v)?;
+                // 149: [1, 24, 1014]
+                let v = mul(1, 24)?;
+                store(&mut mem, 1014, v)?;
+                // 153: [763, 1, 1022]
+                let v = mul(763, 1)?;
+                store(&mut mem, 1022, v)?;
+                // 157: [0, 31, 1007]
+                let v = add(0, 31)?;
+                store(&mut mem, 1007, v)?;
+                // 161: [1, 221, 1024]
+                let v = mul(1, 221)?;
+                store(&mut mem, 1024, v)?;
+                // 165: [0, 32, 1002]
+                let v = add(0, 32)?;
+                store(&mut mem, 1002, v)?;
+                // 169: [1, 29, 1004]
+                let v = mul(1, 29)?;
+                store(&mut mem, 1004, v)?;
+                // 173: [1, 35, 1016]
+                let v = mul(1, 35)?;
+                store(&mut mem, 1016, v)?;
+                // 177: [22, 1, 1015]
+                let v = mul(22, 1)?;
+                store(&mut mem, 1015, v)?;
+                // 181: [0, 27, 1001]
+                let v = add(0, 27)?;
+                store(&mut mem, 1001, v)?;
+                // 185: [9]
+                rel = add(rel, 9)?;
+                // 187: [-6, 26, 63]
+                let v = (load(&mem, add(rel, -6)?)? < 26) as i64;
+                store(&mut mem, 63, v)?;
+                // 191: [63, 199]
+                if load(&mem, 63)? != 0 {
+                    ip = 199;
+                    continue;
+                }
+                ip = 194;
+            }
+            194 => {
+                // 194: [187]
+                output.send(load(&mem, 187)?)?;
+                // 196: [1, 203]
+                ip = 203;
+                continue;
+            }
+            199 => {
+                // 199: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 203;
+            }
+            203 => {
+                // 203: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 207: [19]
+                rel = add(rel, 19)?;
+                // 209: [1, -4]
+                ip = load(&mem, add(rel, -4)?)?;
+                continue;
+            }
+            212 => {
+                // 212: [209]
+                output.send(load(&mem, 209)?)?;
+                // 214: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 218: [0, 221]
+                ip = 221;
+                continue;
+            }
+            221 => {
+                // 221: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 225: [-33]
+                rel = add(rel, -33)?;
+                // 227: [5, 37, 63]
+                let v = (load(&mem, add(rel, 5)?)? < 37) as i64;
+                store(&mut mem, 63, v)?;
+                // 231: [63, 241]
+                if load(&mem, 63)? != 0 {
+                    ip = 241;
+                    continue;
+                }
+                ip = 234;
+            }
+            234 => {
+                // 234: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 238: [0, 243]
+                ip = 243;
+                continue;
+            }
+            241 => {
+                // 241: [227]
+                output.send(load(&mem, 227)?)?;
+                ip = 243;
+            }
+            243 => {
+                // 243: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 247: [16]
+                rel = add(rel, 16)?;
+                // 249: [1, -2, 63]
+                let v = mul(1, load(&mem, add(rel, -2)?)?)?;
+                store(&mut mem, 63, v)?;
+                // 253: [63, 23, 63]
+                let v = (load(&mem, 63)? == 23) as i64;
+                store(&mut mem, 63, v)?;
+                // 257: [63, 269]
+                if load(&mem, 63)? != 0 {
+                    ip = 269;
+                    continue;
+                }
+                ip = 260;
+            }
+            260 => {
+                // 260: [249]
+                output.send(load(&mem, 249)?)?;
+                // 262: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 266: [0, 269]
+                ip = 269;
+                continue;
+            }
+            269 => {
+                // 269: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 273: [16]
+                rel = add(rel, 16)?;
+                // 275: [0, 0]
+                ip = load(&mem, add(rel, 0)?)?;
+                continue;
+            }
+            278 => {
+                // 278: [0, 287]
+                ip = 287;
+                continue;
+            }
+            281 => {
+                // 281: [275]
+                output.send(load(&mem, 275)?)?;
+                // 283: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 287;
+            }
+            287 => {
+                // 287: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 291: [-11]
+                rel = add(rel, -11)?;
+                // 293: [40, 0, 0]
+                let v = add(40, 0)?;
+                let d = add(rel, 0)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 293, 21101, 297);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 297: [1016, 38, 63]
+                let v = (load(&mem, 1016)? == 38) as i64;
+                store(&mut mem, 63, v)?;
+                // 301: [63, 311]
+                if load(&mem, 63)? != 0 {
+                    ip = 311;
+                    continue;
+                }
+                ip = 304;
+            }
+            304 => {
+                // 304: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 308: [1, 313]
+                ip = 313;
+                continue;
+            }
+            311 => {
+                // 311: [293]
+                output.send(load(&mem, 293)?)?;
+                ip = 313;
+            }
+            313 => {
+                // 313: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 317: [4]
+                rel = add(rel, 4)?;
+                // 319: [41, 40, -9]
+                let v = (41 < 40) as i64;
+                let d = add(rel, -9)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 319, 21107, 323);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 323: [1011, 329]
+                if load(&mem, 1011)? != 0 {
+                    ip = 329;
+                    continue;
+                }
+                ip = 326;
+            }
+            326 => {
+                // 326: [1, 335]
+                ip = 335;
+                continue;
+            }
+            329 => {
+                // 329: [319]
+                output.send(load(&mem, 319)?)?;
+                // 331: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 335;
+            }
+            335 => {
+                // 335: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 339: [-14]
+                rel = add(rel, -14)?;
+                // 341: [42, 42, 5]
+                let v = (42 == 42) as i64;
+                let d = add(rel, 5)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 341, 21108, 345);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 345: [1011, 353]
+                if load(&mem, 1011)? != 0 {
+                    ip = 353;
+                    continue;
+                }
+                ip = 348;
+            }
+            348 => {
+                // 348: [341]
+                output.send(load(&mem, 341)?)?;
+                // 350: [0, 357]
+                ip = 357;
+                continue;
+            }
+            353 => {
+                // 353: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 357;
+            }
+            357 => {
+                // 357: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 361: [2]
+                rel = add(rel, 2)?;
+                // 363: [33, 0, 63]
+                let v = (33 < load(&mem, add(rel, 0)?)?) as i64;
+                store(&mut mem, 63, v)?;
+                // 367: [63, 379]
+                if load(&mem, 63)? != 0 {
+                    ip = 379;
+                    continue;
+                }
+                ip = 370;
+            }
+            370 => {
+                // 370: [363]
+                output.send(load(&mem, 363)?)?;
+                // 372: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 376: [1, 379]
+                ip = 379;
+                continue;
+            }
+            379 => {
+                // 379: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 383: [-7]
+                rel = add(rel, -7)?;
+                // 385: [2, 0, 63]
+                let v = add(load(&mem, add(rel, 2)?)?, 0)?;
+                store(&mut mem, 63, v)?;
+                // 389: [63, 25, 63]
+                let v = (load(&mem, 63)? == 25) as i64;
+                store(&mut mem, 63, v)?;
+                // 393: [63, 401]
+                if load(&mem, 63)? != 0 {
+                    ip = 401;
+                    continue;
+                }
+                ip = 396;
+            }
+            396 => {
+                // 396: [385]
+                output.send(load(&mem, 385)?)?;
+                // 398: [1, 405]
+                ip = 405;
+                continue;
+            }
+            401 => {
+                // 401: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 405;
+            }
+            405 => {
+                // 405: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 409: [11]
+                rel = add(rel, 11)?;
+                // 411: [-8, 0, 63]
+                let v = add(load(&mem, add(rel, -8)?)?, 0)?;
+                store(&mut mem, 63, v)?;
+                // 415: [63, 28, 63]
+                let v = (load(&mem, 63)? == 28) as i64;
+                store(&mut mem, 63, v)?;
+                // 419: [63, 429]
+                if load(&mem, 63)? != 0 {
+                    ip = 429;
+                    continue;
+                }
+                ip = 422;
+            }
+            422 => {
+                // 422: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 426: [0, 431]
+                ip = 431;
+                continue;
+            }
+            429 => {
+                // 429: [411]
+                output.send(load(&mem, 411)?)?;
+                ip = 431;
+            }
+            431 => {
+                // 431: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 435: [-7]
+                rel = add(rel, -7)?;
+                // 437: [26, 1, 63]
+                let v = (26 == load(&mem, add(rel, 1)?)?) as i64;
+                store(&mut mem, 63, v)?;
+                // 441: [63, 449]
+                if load(&mem, 63)? != 0 {
+                    ip = 449;
+                    continue;
+                }
+                ip = 444;
+            }
+            444 => {
+                // 444: [437]
+                output.send(load(&mem, 437)?)?;
+                // 446: [1, 453]
+                ip = 453;
+                continue;
+            }
+            449 => {
+                // 449: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 453;
+            }
+            453 => {
+                // 453: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 457: [9]
+                rel = add(rel, 9)?;
+                // 459: [7, 465]
+                if load(&mem, add(rel, 7)?)? == 0 {
+                    ip = 465;
+                    continue;
+                }
+                ip = 462;
+            }
+            462 => {
+                // 462: [1, 471]
+                ip = 471;
+                continue;
+            }
+            465 => {
+                // 465: [459]
+                output.send(load(&mem, 459)?)?;
+                // 467: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 471;
+            }
+            471 => {
+                // 471: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 475: [4]
+                rel = add(rel, 4)?;
+                // 477: [43, 1, -3]
+                let v = mul(43, 1)?;
+                let d = add(rel, -3)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 477, 21102, 481);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 481: [1015, 42, 63]
+                let v = (load(&mem, 1015)? == 42) as i64;
+                store(&mut mem, 63, v)?;
+                // 485: [63, 491]
+                if load(&mem, 63)? != 0 {
+                    ip = 491;
+                    continue;
+                }
+                ip = 488;
+            }
+            488 => {
+                // 488: [0, 497]
+                ip = 497;
+                continue;
+            }
+            491 => {
+                // 491: [477]
+                output.send(load(&mem, 477)?)?;
+                // 493: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 497;
+            }
+            497 => {
+                // 497: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 501: [7]
+                rel = add(rel, 7)?;
+                // 503: [44, 43, -7]
+                let v = (44 == 43) as i64;
+                let d = add(rel, -7)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 503, 21108, 507);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 507: [1018, 517]
+                if load(&mem, 1018)? != 0 {
+                    ip = 517;
+                    continue;
+                }
+                ip = 510;
+            }
+            510 => {
+                // 510: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 514: [1, 519]
+                ip = 519;
+                continue;
+            }
+            517 => {
+                // 517: [503]
+                output.send(load(&mem, 503)?)?;
+                ip = 519;
+            }
+            519 => {
+                // 519: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 523: [-28]
+                rel = add(rel, -28)?;
+                // 525: [0, 7, 63]
+                let v = add(0, load(&mem, add(rel, 7)?)?)?;
+                store(&mut mem, 63, v)?;
+                // 529: [63, 29, 63]
+                let v = (load(&mem, 63)? == 29) as i64;
+                store(&mut mem, 63, v)?;
+                // 533: [63, 545]
+                if load(&mem, 63)? != 0 {
+                    ip = 545;
+                    continue;
+                }
+                ip = 536;
+            }
+            536 => {
+                // 536: [525]
+                output.send(load(&mem, 525)?)?;
+                // 538: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 542: [1, 545]
+                ip = 545;
+                continue;
+            }
+            545 => {
+                // 545: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 549: [11]
+                rel = add(rel, 11)?;
+                // 551: [28, -7, 63]
+                let v = (28 < load(&mem, add(rel, -7)?)?) as i64;
+                store(&mut mem, 63, v)?;
+                // 555: [63, 561]
+                if load(&mem, 63)? != 0 {
+                    ip = 561;
+                    continue;
+                }
+                ip = 558;
+            }
+            558 => {
+                // 558: [1, 567]
+                ip = 567;
+                continue;
+            }
+            561 => {
+                // 561: [551]
+                output.send(load(&mem, 551)?)?;
+                // 563: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 567;
+            }
+            567 => {
+                // 567: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 571: [-4]
+                rel = add(rel, -4)?;
+                // 573: [0, -1, 63]
+                let v = add(0, load(&mem, add(rel, -1)?)?)?;
+                store(&mut mem, 63, v)?;
+                // 577: [63, 26, 63]
+                let v = (load(&mem, 63)? == 26) as i64;
+                store(&mut mem, 63, v)?;
+                // 581: [63, 587]
+                if load(&mem, 63)? != 0 {
+                    ip = 587;
+                    continue;
+                }
+                ip = 584;
+            }
+            584 => {
+                // 584: [1, 593]
+                ip = 593;
+                continue;
+            }
+            587 => {
+                // 587: [573]
+                output.send(load(&mem, 573)?)?;
+                // 589: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 593;
+            }
+            593 => {
+                // 593: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 597: [9]
+                rel = add(rel, 9)?;
+                // 599: [7, 607]
+                if load(&mem, add(rel, 7)?)? == 0 {
+                    ip = 607;
+                    continue;
+                }
+                ip = 602;
+            }
+            602 => {
+                // 602: [599]
+                output.send(load(&mem, 599)?)?;
+                // 604: [1, 611]
+                ip = 611;
+                continue;
+            }
+            607 => {
+                // 607: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 611;
+            }
+            611 => {
+                // 611: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 615: [-10]
+                rel = add(rel, -10)?;
+                // 617: [1, 27, 63]
+                let v = (load(&mem, add(rel, 1)?)? == 27) as i64;
+                store(&mut mem, 63, v)?;
+                // 621: [63, 627]
+                if load(&mem, 63)? != 0 {
+                    ip = 627;
+                    continue;
+                }
+                ip = 624;
+            }
+            624 => {
+                // 624: [0, 633]
+                ip = 633;
+                continue;
+            }
+            627 => {
+                // 627: [617]
+                output.send(load(&mem, 617)?)?;
+                // 629: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 633;
+            }
+            633 => {
+                // 633: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 637: [26]
+                rel = add(rel, 26)?;
+                // 639: [-9, 649]
+                if load(&mem, add(rel, -9)?)? != 0 {
+                    ip = 649;
+                    continue;
+                }
+                ip = 642;
+            }
+            642 => {
+                // 642: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 646: [0, 651]
+                ip = 651;
+                continue;
+            }
+            649 => {
+                // 649: [639]
+                output.send(load(&mem, 639)?)?;
+                ip = 651;
+            }
+            651 => {
+                // 651: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 655: [-20]
+                rel = add(rel, -20)?;
+                // 657: [0, 23, 63]
+                let v = (load(&mem, add(rel, 0)?)? == 23) as i64;
+                store(&mut mem, 63, v)?;
+                // 661: [63, 669]
+                if load(&mem, 63)? != 0 {
+                    ip = 669;
+                    continue;
+                }
+                ip = 664;
+            }
+            664 => {
+                // 664: [657]
+                output.send(load(&mem, 657)?)?;
+                // 666: [1, 673]
+                ip = 673;
+                continue;
+            }
+            669 => {
+                // 669: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 673;
+            }
+            673 => {
+                // 673: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 677: [-7]
+                rel = add(rel, -7)?;
+                // 679: [1, 1, 63]
+                let v = mul(1, load(&mem, add(rel, 1)?)?)?;
+                store(&mut mem, 63, v)?;
+                // 683: [63, 28, 63]
+                let v = (load(&mem, 63)? == 28) as i64;
+                store(&mut mem, 63, v)?;
+                // 687: [63, 693]
+                if load(&mem, 63)? != 0 {
+                    ip = 693;
+                    continue;
+                }
+                ip = 690;
+            }
+            690 => {
+                // 690: [1, 699]
+                ip = 699;
+                continue;
+            }
+            693 => {
+                // 693: [679]
+                output.send(load(&mem, 679)?)?;
+                // 695: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 699;
+            }
+            699 => {
+                // 699: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 703: [18]
+                rel = add(rel, 18)?;
+                // 705: [45, 1, -6]
+                let v = mul(45, 1)?;
+                let d = add(rel, -6)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 705, 21102, 709);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 709: [1014, 45, 63]
+                let v = (load(&mem, 1014)? == 45) as i64;
+                store(&mut mem, 63, v)?;
+                // 713: [63, 725]
+                if load(&mem, 63)? != 0 {
+                    ip = 725;
+                    continue;
+                }
+                ip = 716;
+            }
+            716 => {
+                // 716: [705]
+                output.send(load(&mem, 705)?)?;
+                // 718: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 722: [0, 725]
+                ip = 725;
+                continue;
+            }
+            725 => {
+                // 725: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 729: [-23]
+                rel = add(rel, -23)?;
+                // 731: [6, 1, 63]
+                let v = mul(load(&mem, add(rel, 6)?)?, 1)?;
+                store(&mut mem, 63, v)?;
+                // 735: [63, 25, 63]
+                let v = (load(&mem, 63)? == 25) as i64;
+                store(&mut mem, 63, v)?;
+                // 739: [63, 751]
+                if load(&mem, 63)? != 0 {
+                    ip = 751;
+                    continue;
+                }
+                ip = 742;
+            }
+            742 => {
+                // 742: [731]
+                output.send(load(&mem, 731)?)?;
+                // 744: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 748: [0, 751]
+                ip = 751;
+                continue;
+            }
+            751 => {
+                // 751: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 755: [20]
+                rel = add(rel, 20)?;
+                // 757: [1, 6]
+                ip = load(&mem, add(rel, 6)?)?;
+                continue;
+            }
+            760 => {
+                // 760: [0, 769]
+                ip = 769;
+                continue;
+            }
+            763 => {
+                // 763: [757]
+                output.send(load(&mem, 757)?)?;
+                // 765: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 769;
+            }
+            769 => {
+                // 769: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 773: [-22]
+                rel = add(rel, -22)?;
+                // 775: [39, 10, 63]
+                let v = (39 == load(&mem, add(rel, 10)?)?) as i64;
+                store(&mut mem, 63, v)?;
+                // 779: [63, 789]
+                if load(&mem, 63)? != 0 {
+                    ip = 789;
+                    continue;
+                }
+                ip = 782;
+            }
+            782 => {
+                // 782: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 786: [0, 791]
+                ip = 791;
+                continue;
+            }
+            789 => {
+                // 789: [775]
+                output.send(load(&mem, 775)?)?;
+                ip = 791;
+            }
+            791 => {
+                // 791: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 795: [3]
+                rel = add(rel, 3)?;
+                // 797: [6, 1, 63]
+                let v = mul(load(&mem, add(rel, 6)?)?, 1)?;
+                store(&mut mem, 63, v)?;
+                // 801: [63, 32, 63]
+                let v = (load(&mem, 63)? == 32) as i64;
+                store(&mut mem, 63, v)?;
+                // 805: [63, 815]
+                if load(&mem, 63)? != 0 {
+                    ip = 815;
+                    continue;
+                }
+                ip = 808;
+            }
+            808 => {
+                // 808: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 812: [1, 817]
+                ip = 817;
+                continue;
+            }
+            815 => {
+                // 815: [797]
+                output.send(load(&mem, 797)?)?;
+                ip = 817;
+            }
+            817 => {
+                // 817: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 821: [23]
+                rel = add(rel, 23)?;
+                // 823: [46, 47, -9]
+                let v = (46 < 47) as i64;
+                let d = add(rel, -9)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 823, 21107, 827);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 827: [1012, 835]
+                if load(&mem, 1012)? != 0 {
+                    ip = 835;
+                    continue;
+                }
+                ip = 830;
+            }
+            830 => {
+                // 830: [823]
+                output.send(load(&mem, 823)?)?;
+                // 832: [0, 839]
+                ip = 839;
+                continue;
+            }
+            835 => {
+                // 835: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 839;
+            }
+            839 => {
+                // 839: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 843: [1]
+                rel = add(rel, 1)?;
+                // 845: [-1, 853]
+                if load(&mem, add(rel, -1)?)? != 0 {
+                    ip = 853;
+                    continue;
+                }
+                ip = 848;
+            }
+            848 => {
+                // 848: [845]
+                output.send(load(&mem, 845)?)?;
+                // 850: [1, 857]
+                ip = 857;
+                continue;
+            }
+            853 => {
+                // 853: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 857;
+            }
+            857 => {
+                // 857: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 861: [-2]
+                rel = add(rel, -2)?;
+                // 863: [0, 8]
+                ip = load(&mem, add(rel, 8)?)?;
+                continue;
+            }
+            866 => {
+                // 866: [863]
+                output.send(load(&mem, 863)?)?;
+                // 868: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                // 872: [1, 875]
+                ip = 875;
+                continue;
+            }
+            875 => {
+                // 875: [64, 2, 64]
+                let v = mul(load(&mem, 64)?, 2)?;
+                store(&mut mem, 64, v)?;
+                // 879: [-8]
+                rel = add(rel, -8)?;
+                // 881: [47, 0, -2]
+                let v = add(47, 0)?;
+                let d = add(rel, -2)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 881, 21101, 885);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 885: [1010, 47, 63]
+                let v = (load(&mem, 1010)? == 47) as i64;
+                store(&mut mem, 63, v)?;
+                // 889: [63, 897]
+                if load(&mem, 63)? != 0 {
+                    ip = 897;
+                    continue;
+                }
+                ip = 892;
+            }
+            892 => {
+                // 892: [881]
+                output.send(load(&mem, 881)?)?;
+                // 894: [0, 901]
+                ip = 901;
+                continue;
+            }
+            897 => {
+                // 897: [64, 1, 64]
+                let v = add(load(&mem, 64)?, 1)?;
+                store(&mut mem, 64, v)?;
+                ip = 901;
+            }
+            901 => {
+                // 901: [64]
+                output.send(load(&mem, 64)?)?;
+                // 903: []
+                return Ok(mem);
+            }
+            904 => {
+                // 904: [27, 1, 1]
+                let v = mul(27, 1)?;
+                let d = add(rel, 1)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 904, 21102, 908);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 908: [0, 915, 0]
+                let v = add(0, 915)?;
+                let d = add(rel, 0)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 908, 21101, 912);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 912: [1, 922]
+                ip = 922;
+                continue;
+            }
+            915 => {
+                // 915: [1, 27810, 1]
+                let v = add(load(&mem, add(rel, 1)?)?, 27810)?;
+                let d = add(rel, 1)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 915, 21201, 919);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 919: [1]
+                output.send(load(&mem, add(rel, 1)?)?)?;
+                // 921: []
+                return Ok(mem);
+            }
+            922 => {
+                // 922: [3]
+                rel = add(rel, 3)?;
+                // 924: [-2, 3, 63]
+                let v = (load(&mem, add(rel, -2)?)? < 3) as i64;
+                store(&mut mem, 63, v)?;
+                // 928: [63, 964]
+                if load(&mem, 63)? != 0 {
+                    ip = 964;
+                    continue;
+                }
+                ip = 931;
+            }
+            931 => {
+                // 931: [-2, -1, 1]
+                let v = add(load(&mem, add(rel, -2)?)?, -1)?;
+                let d = add(rel, 1)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 931, 21201, 935);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 935: [1, 942, 0]
+                let v = mul(1, 942)?;
+                let d = add(rel, 0)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 935, 21102, 939);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 939: [0, 922]
+                ip = 922;
+                continue;
+            }
+            942 => {
+                // 942: [0, 1, -1]
+                let v = add(0, load(&mem, add(rel, 1)?)?)?;
+                let d = add(rel, -1)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 942, 22101, 946);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 946: [-2, -3, 1]
+                let v = add(load(&mem, add(rel, -2)?)?, -3)?;
+                let d = add(rel, 1)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 946, 21201, 950);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 950: [957, 0, 0]
+                let v = add(957, 0)?;
+                let d = add(rel, 0)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 950, 21101, 954);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 954: [0, 922]
+                ip = 922;
+                continue;
+            }
+            957 => {
+                // 957: [1, -1, -2]
+                let v = add(load(&mem, add(rel, 1)?)?, load(&mem, add(rel, -1)?)?)?;
+                let d = add(rel, -2)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 957, 22201, 961);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                // 961: [0, 968]
+                ip = 968;
+                continue;
+            }
+            964 => {
+                // 964: [0, -2, -2]
+                let v = add(0, load(&mem, add(rel, -2)?)?)?;
+                let d = add(rel, -2)?;
+                store(&mut mem, d, v)?;
+                if is_code(d) {
+                    ip = resume_at(&mem, 964, 22101, 968);
+                    return fallback(mem, ip, rel, input, output);
+                }
+                ip = 968;
+            }
+            968 => {
+                // 968: [-3]
+                rel = add(rel, -3)?;
+                // 970: [0, 0]
+                ip = load(&mem, add(rel, 0)?)?;
+                continue;
+            }
+            _ => return fallback(mem, ip, rel, input, output),
+        }
+    }
+}
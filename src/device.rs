@@ -0,0 +1,180 @@
+use crate::day2::{read_comma_file, Error, Stepper, Yield};
+use crate::day9::build_machine;
+
+/// Extra zeroed memory given to device programs beyond their own length
+const SCRATCH: usize = 4096;
+
+/// The I/O protocol an Intcode program speaks to the hardware it controls: each command is one
+/// input value, and the program answers in groups of `ARITY` output values.
+pub trait Device {
+    type Command;
+    type Event;
+
+    /// Output values making up one event
+    const ARITY: usize;
+
+    fn encode(&self, command: &Self::Command) -> i64;
+
+    /// Turn a full group of outputs into an event. Values outside the protocol should be
+    /// reported with `Error::BadOutput`.
+    fn decode(&self, output: &[i64]) -> Result<Self::Event, Error>;
+}
+
+/// What a device program did when it was last resumed
+#[derive(Debug, PartialEq, Eq)]
+pub enum Poll<E> {
+    /// The program finished a group of outputs
+    Event(E),
+    /// The program is waiting for the next command
+    NeedsCommand,
+    Halted,
+}
+
+/// Runs an Intcode program on the calling thread and speaks a device protocol to it
+pub struct Driver<D: Device> {
+    device: D,
+    stepper: Stepper,
+    output: Vec<i64>,
+}
+
+impl<D: Device> Driver<D> {
+    /// Boot `program` on the full day 9 ISA
+    pub fn new(device: D, mut program: Vec<i64>) -> Self {
+        program.extend(vec![0; SCRATCH]);
+        Driver::with_stepper(device, Stepper::new(build_machine(program)))
+    }
+
+    pub fn load(device: D, filename: &str) -> Result<Self, Error> {
+        Ok(Driver::new(device, read_comma_file(filename)?))
+    }
+
+    /// Drive a machine that is already set up, such as a fork of another driver's
+    pub fn with_stepper(device: D, stepper: Stepper) -> Self {
+        Driver {
+            device,
+            stepper,
+            output: Vec::with_capacity(D::ARITY),
+        }
+    }
+
+    /// Queue a command for the program's next input
+    pub fn send(&mut self, command: &D::Command) {
+        let value = self.device.encode(command);
+        self.stepper.push_input(value);
+    }
+
+    /// Run until the program completes an event, wants a command or halts. Halting part-way
+    /// through an event is a protocol error.
+    pub fn poll(&mut self) -> Result<Poll<D::Event>, Error> {
+        loop {
+            match self.stepper.resume()? {
+                Yield::Output(v) => {
+                    self.output.push(v);
+                    if self.output.len() == D::ARITY {
+                        let event = self.device.decode(&self.output);
+                        self.output.clear();
+                        return event.map(Poll::Event);
+                    }
+                }
+                Yield::NeedsInput if self.output.is_empty() => return Ok(Poll::NeedsCommand),
+                Yield::NeedsInput => return Err(Error::TruncatedOutput(self.output.len())),
+                Yield::Halted if self.output.is_empty() => return Ok(Poll::Halted),
+                Yield::Halted => return Err(Error::TruncatedOutput(self.output.len())),
+                Yield::OutOfSteps => continue,
+            }
+        }
+    }
+
+    /// Send `command`, then wait for the event it produces
+    pub fn request(&mut self, command: &D::Command) -> Result<D::Event, Error> {
+        self.send(command);
+        match self.poll()? {
+            Poll::Event(e) => Ok(e),
+            Poll::NeedsCommand => Err(Error::NeedsInput),
+            Poll::Halted => Err(Error::NotRunning),
+        }
+    }
+
+    /// An independent copy of the device and its program, picking up where this one is
+    pub fn fork(&self) -> Self
+    where
+        D: Clone,
+    {
+        Driver {
+            device: self.device.clone(),
+            stepper: self.stepper.fork(),
+            output: self.output.clone(),
+        }
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    pub fn stepper(&self) -> &Stepper {
+        &self.stepper
+    }
+
+    pub fn stepper_mut(&mut self) -> &mut Stepper {
+        &mut self.stepper
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Answers each command with the command and its double
+    #[derive(Clone)]
+    struct Doubler;
+
+    impl Device for Doubler {
+        type Command = i64;
+        type Event = (i64, i64);
+        const ARITY: usize = 2;
+
+        fn encode(&self, command: &i64) -> i64 {
+            *command
+        }
+
+        fn decode(&self, output: &[i64]) -> Result<(i64, i64), Error> {
+            if output[1] == output[0] * 2 {
+                Ok((output[0], output[1]))
+            } else {
+                Err(Error::BadOutput(output[1]))
+            }
+        }
+    }
+
+    #[test]
+    fn protocol() {
+        // loop { in @20; out @20; @20 *= 2; out @20 }
+        let program = vec![3, 20, 4, 20, 1002, 20, 2, 20, 4, 20, 1105, 1, 0];
+        let mut d = Driver::new(Doubler, program);
+        assert_eq!(d.poll(), Ok(Poll::NeedsCommand));
+        assert_eq!(d.request(&21), Ok((21, 42)));
+        d.send(&-3);
+        assert_eq!(d.poll(), Ok(Poll::Event((-3, -6))));
+        assert_eq!(d.poll(), Ok(Poll::NeedsCommand));
+
+        let mut f = d.fork();
+        assert_eq!(f.request(&5), Ok((5, 10)));
+        assert_eq!(d.request(&6), Ok((6, 12)));
+    }
+
+    #[test]
+    fn broken_protocol() {
+        // out 1; out 3; out 5; halt
+        let mut d = Driver::new(Doubler, vec![104, 1, 104, 3, 104, 5, 99]);
+        assert_eq!(d.poll(), Err(Error::BadOutput(3)));
+        assert_eq!(d.poll(), Err(Error::TruncatedOutput(1)));
+
+        // out 1; in
+        let mut d = Driver::new(Doubler, vec![104, 1, 3, 0, 99]);
+        assert_eq!(d.poll(), Err(Error::TruncatedOutput(1)));
+    }
+}
@@ -0,0 +1,648 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+use std::iter::FromIterator;
+
+/// (x, y)
+pub type Point = (i64, i64);
+
+/// Offsets to the horizontal and vertical neighbors
+pub const NEIGHBORS4: [Point; 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+/// Offsets to all eight neighbors, diagonals included
+pub const NEIGHBORS8: [Point; 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+pub fn neighbors4(p: Point) -> impl Iterator<Item = Point> {
+    NEIGHBORS4.iter().map(move |d| (p.0 + d.0, p.1 + d.1))
+}
+
+pub fn neighbors8(p: Point) -> impl Iterator<Item = Point> {
+    NEIGHBORS8.iter().map(move |d| (p.0 + d.0, p.1 + d.1))
+}
+
+/// Inclusive rectangle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn point(p: Point) -> Self {
+        Bounds { min: p, max: p }
+    }
+
+    /// The smallest bounds holding every point, or `None` if there are none
+    pub fn of<I: IntoIterator<Item = Point>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let mut b = Bounds::point(points.next()?);
+        for p in points {
+            b.include(p);
+        }
+        Some(b)
+    }
+
+    /// Grow to hold `p`
+    pub fn include(&mut self, p: Point) {
+        self.min = (self.min.0.min(p.0), self.min.1.min(p.1));
+        self.max = (self.max.0.max(p.0), self.max.1.max(p.1));
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        (self.min.0..=self.max.0).contains(&p.0) && (self.min.1..=self.max.1).contains(&p.1)
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.0 - self.min.0 + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.1 - self.min.1 + 1) as usize
+    }
+}
+
+/// Operations shared by the sparse and dense grids
+pub trait Grid<T> {
+    fn get(&self, p: Point) -> Option<&T>;
+
+    fn get_mut(&mut self, p: Point) -> Option<&mut T>;
+
+    fn set(&mut self, p: Point, tile: T);
+
+    /// Rectangle holding every tile ever set, or `None` if nothing has been
+    fn bounds(&self) -> Option<Bounds>;
+
+    /// The horizontal and vertical neighbors of `p` that hold a tile
+    fn around4(&self, p: Point) -> Vec<(Point, &T)> {
+        neighbors4(p)
+            .filter_map(|n| self.get(n).map(|t| (n, t)))
+            .collect()
+    }
+
+    /// All eight neighbors of `p` that hold a tile
+    fn around8(&self, p: Point) -> Vec<(Point, &T)> {
+        neighbors8(p)
+            .filter_map(|n| self.get(n).map(|t| (n, t)))
+            .collect()
+    }
+
+    /// Draw the grid one line per row, mapping each cell (`None` where nothing is set) through
+    /// `tile`. Rows run top to bottom by increasing y, or decreasing y if `y_up`.
+    fn render<D, F>(&self, y_up: bool, tile: F) -> String
+    where
+        D: Display,
+        F: Fn(Option<&T>) -> D,
+    {
+        let mut buf = String::new();
+        let b = match self.bounds() {
+            Some(b) => b,
+            None => return buf,
+        };
+        let rows: Vec<i64> = if y_up {
+            (b.min.1..=b.max.1).rev().collect()
+        } else {
+            (b.min.1..=b.max.1).collect()
+        };
+        for y in rows {
+            for x in b.min.0..=b.max.0 {
+                write!(buf, "{}", tile(self.get((x, y)))).unwrap();
+            }
+            buf.push('\n');
+        }
+        buf
+    }
+}
+
+/// Each character of `text` that `tile` maps to something, at (column, line)
+fn cells<T, F>(text: &str, mut tile: F) -> Vec<(Point, T)>
+where
+    F: FnMut(char) -> Option<T>,
+{
+    text.lines()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .map(move |(x, c)| ((x as i64, y as i64), c))
+        })
+        .filter_map(|(p, c)| tile(c).map(|t| (p, t)))
+        .collect()
+}
+
+/// Grid backed by a map, for areas that are explored rather than known up front
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    tiles: HashMap<Point, T>,
+    bounds: Option<Bounds>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        SparseGrid {
+            tiles: HashMap::new(),
+            bounds: None,
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a grid from text, one row per line. Characters `tile` maps to `None` are left unset.
+    pub fn parse<F: FnMut(char) -> Option<T>>(text: &str, tile: F) -> Self {
+        cells(text, tile).into_iter().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        self.tiles.contains_key(&p)
+    }
+
+    /// The tile at `p`, setting it to `default()` first if nothing is there
+    pub fn get_or_insert_with<F: FnOnce() -> T>(&mut self, p: Point, default: F) -> &mut T {
+        self.bounds
+            .get_or_insert_with(|| Bounds::point(p))
+            .include(p);
+        self.tiles.entry(p).or_insert_with(default)
+    }
+
+    /// Take the tile at `p` out. The bounds do not shrink.
+    pub fn remove(&mut self, p: Point) -> Option<T> {
+        self.tiles.remove(&p)
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.tiles.keys().cloned()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.tiles.values()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.tiles.iter().map(|(p, t)| (*p, t))
+    }
+}
+
+impl<T> Grid<T> for SparseGrid<T> {
+    fn get(&self, p: Point) -> Option<&T> {
+        self.tiles.get(&p)
+    }
+
+    fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.tiles.get_mut(&p)
+    }
+
+    fn set(&mut self, p: Point, tile: T) {
+        self.bounds
+            .get_or_insert_with(|| Bounds::point(p))
+            .include(p);
+        self.tiles.insert(p, tile);
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+}
+
+impl<T> FromIterator<(Point, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point, T)>>(iter: I) -> Self {
+        let mut g = SparseGrid::new();
+        for (p, t) in iter {
+            g.set(p, t);
+        }
+        g
+    }
+}
+
+/// Grid backed by a row-major vector, for areas of roughly known size. Every cell inside the
+/// bounds holds a tile, `T::default()` until set; setting outside the bounds grows them. Storage
+/// grows with room to spare, so setting cells one past the edge at a time stays cheap.
+#[derive(Clone, Debug)]
+pub struct DenseGrid<T> {
+    cells: Vec<T>,
+    bounds: Option<Bounds>,
+    /// The area `cells` is laid out over, holding `bounds`
+    alloc: Option<Bounds>,
+}
+
+impl<T> Default for DenseGrid<T> {
+    fn default() -> Self {
+        DenseGrid {
+            cells: Vec::new(),
+            bounds: None,
+            alloc: None,
+        }
+    }
+}
+
+impl<T: Clone + Default> DenseGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A grid covering `bounds`, filled with `T::default()`
+    pub fn with_bounds(bounds: Bounds) -> Self {
+        DenseGrid {
+            cells: vec![T::default(); bounds.width() * bounds.height()],
+            bounds: Some(bounds),
+            alloc: Some(bounds),
+        }
+    }
+
+    /// Read a grid from text, one row per line. Characters `tile` maps to `None` are left at
+    /// `T::default()`.
+    pub fn parse<F: FnMut(char) -> Option<T>>(text: &str, tile: F) -> Self {
+        cells(text, tile).into_iter().collect()
+    }
+
+    fn index(&self, p: Point) -> Option<usize> {
+        let (b, a) = (self.bounds?, self.alloc?);
+        if b.contains(p) {
+            Some((p.1 - a.min.1) as usize * a.width() + (p.0 - a.min.0) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Every cell inside the bounds, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.bounds
+            .into_iter()
+            .flat_map(|b| {
+                (b.min.1..=b.max.1).flat_map(move |y| (b.min.0..=b.max.0).map(move |x| (x, y)))
+            })
+            .filter_map(move |p| self.get(p).map(|t| (p, t)))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_ {
+        self.iter().map(|(_, t)| t)
+    }
+
+    /// Grow the bounds to hold `p`. When that runs past the storage, re-lay the cells out over
+    /// an area that extends each side `p` is beyond by the old size again, doubling it.
+    fn grow(&mut self, p: Point) {
+        let mut bounds = self.bounds.unwrap_or_else(|| Bounds::point(p));
+        bounds.include(p);
+        let mut alloc = match self.alloc {
+            Some(a) if a.contains(p) => {
+                self.bounds = Some(bounds);
+                return;
+            }
+            Some(a) => a,
+            None => bounds,
+        };
+        let (w, h) = (alloc.width() as i64, alloc.height() as i64);
+        if p.0 < alloc.min.0 {
+            alloc.min.0 = p.0.min(alloc.min.0 - w);
+        }
+        if p.0 > alloc.max.0 {
+            alloc.max.0 = p.0.max(alloc.max.0 + w);
+        }
+        if p.1 < alloc.min.1 {
+            alloc.min.1 = p.1.min(alloc.min.1 - h);
+        }
+        if p.1 > alloc.max.1 {
+            alloc.max.1 = p.1.max(alloc.max.1 + h);
+        }
+
+        let mut grown = DenseGrid::with_bounds(alloc);
+        for (q, t) in self.iter() {
+            if let Some(i) = grown.index(q) {
+                grown.cells[i] = t.clone();
+            }
+        }
+        grown.bounds = Some(bounds);
+        *self = grown;
+    }
+}
+
+/// Grids are equal when their bounds and the cells inside them are, however much storage each
+/// has to spare
+impl<T: Clone + Default + PartialEq> PartialEq for DenseGrid<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bounds == other.bounds && self.values().eq(other.values())
+    }
+}
+
+impl<T: Clone + Default + Eq> Eq for DenseGrid<T> {}
+
+impl<T: Clone + Default> Grid<T> for DenseGrid<T> {
+    fn get(&self, p: Point) -> Option<&T> {
+        self.index(p).map(|i| &self.cells[i])
+    }
+
+    fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.index(p).map(move |i| &mut self.cells[i])
+    }
+
+    fn set(&mut self, p: Point, tile: T) {
+        if self.index(p).is_none() {
+            self.grow(p);
+        }
+        if let Some(i) = self.index(p) {
+            self.cells[i] = tile;
+        }
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+}
+
+impl<T: Clone + Default> FromIterator<(Point, T)> for DenseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point, T)>>(iter: I) -> Self {
+        let tiles: Vec<(Point, T)> = iter.into_iter().collect();
+        let mut g = match Bounds::of(tiles.iter().map(|(p, _)| *p)) {
+            Some(b) => DenseGrid::with_bounds(b),
+            None => DenseGrid::new(),
+        };
+        for (p, t) in tiles {
+            g.set(p, t);
+        }
+        g
+    }
+}
+
+/// Tiles that can be written to a map file, each drawn as one character
+pub trait Glyph: Copy + 'static {
+    /// Every tile, in the order the legend lists them
+    const ALL: &'static [Self];
+
+    fn glyph(self) -> char;
+
+    /// What the legend calls the tile. Loading matches tiles by name, so a map file may redraw
+    /// them with other characters.
+    fn name(self) -> &'static str;
+}
+
+/// Why a map file could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The `origin` line is missing or malformed
+    NoOrigin,
+    /// Legend line that is not a character and a known tile name
+    BadLegend(String),
+    /// Character at this point is not in the legend
+    UnknownGlyph(char, Point),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl<T: Glyph> SparseGrid<T> {
+    /// The grid as a map file: a `legend` line followed by one `<char> <name>` line per tile,
+    /// then `origin x y` giving the point of the first character of the first row, then the rows
+    /// top to bottom by increasing y. Unset cells are spaces.
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("legend\n");
+        for t in T::ALL {
+            writeln!(text, "{} {}", t.glyph(), t.name()).unwrap();
+        }
+        let origin = self.bounds.map_or((0, 0), |b| b.min);
+        writeln!(text, "origin {} {}", origin.0, origin.1).unwrap();
+        text + &self.render(false, |t| t.map_or(' ', |t| t.glyph()))
+    }
+
+    /// Read a map file written by `to_text`
+    pub fn from_text(text: &str) -> Result<Self, Error> {
+        let mut lines = text.lines();
+        let mut legend = HashMap::new();
+        if text.starts_with("legend") {
+            lines.next();
+        }
+        let origin = loop {
+            let line = lines.next().ok_or(Error::NoOrigin)?;
+            if let Some(xy) = line.strip_prefix("origin ") {
+                let xy = xy
+                    .split_whitespace()
+                    .map(|v| v.parse::<i64>().map_err(|_| Error::NoOrigin))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match xy[..] {
+                    [x, y] => break (x, y),
+                    _ => return Err(Error::NoOrigin),
+                }
+            }
+            let mut chars = line.chars();
+            let glyph = chars.next();
+            let name = chars.as_str().trim();
+            match (glyph, T::ALL.iter().find(|t| t.name() == name)) {
+                (Some(c), Some(t)) => legend.insert(c, *t),
+                _ => return Err(Error::BadLegend(line.to_string())),
+            };
+        };
+        if legend.is_empty() {
+            legend = T::ALL.iter().map(|t| (t.glyph(), *t)).collect();
+        }
+
+        let mut grid = SparseGrid::new();
+        for (y, line) in lines.enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let p = (origin.0 + x as i64, origin.1 + y as i64);
+                match legend.get(&c) {
+                    Some(t) => grid.set(p, *t),
+                    None if c == ' ' => {}
+                    None => return Err(Error::UnknownGlyph(c, p)),
+                }
+            }
+        }
+        Ok(grid)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAZE: &str = "#####\n#..##\n#.#.#\n#####\n";
+
+    fn wall(c: char) -> Option<bool> {
+        match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parse_and_render() {
+        let sparse = SparseGrid::parse(MAZE, wall);
+        let dense = DenseGrid::parse(MAZE, wall);
+        let draw = |t: Option<&bool>| match t {
+            Some(true) => '#',
+            Some(false) => '.',
+            None => '?',
+        };
+        assert_eq!(sparse.render(false, draw), MAZE);
+        assert_eq!(dense.render(false, draw), MAZE);
+        assert_eq!(
+            sparse.bounds(),
+            Some(Bounds {
+                min: (0, 0),
+                max: (4, 3)
+            })
+        );
+        assert_eq!(sparse.len(), 20);
+
+        // y grows upward: the last line comes out first
+        assert_eq!(
+            sparse.render(true, draw).lines().next(),
+            MAZE.lines().last()
+        );
+    }
+
+    #[test]
+    fn sparse() {
+        let mut g = SparseGrid::new();
+        assert_eq!(g.bounds(), None);
+        assert_eq!(g.render(false, |_: Option<&char>| 'x'), "");
+        g.set((-2, 1), 'a');
+        g.set((1, -1), 'b');
+        *g.get_or_insert_with((0, 0), || 'c') = 'd';
+        assert_eq!(
+            g.bounds(),
+            Some(Bounds {
+                min: (-2, -1),
+                max: (1, 1)
+            })
+        );
+        assert_eq!(
+            g.render(false, |t| *t.unwrap_or(&' ')),
+            "   b\n  d \na   \n"
+        );
+        assert_eq!(g.around8((-1, 0)).len(), 2);
+        assert_eq!(g.around4((-1, 0)), vec![((0, 0), &'d')]);
+    }
+
+    #[test]
+    fn dense_growth() {
+        let mut g: DenseGrid<u8> = DenseGrid::new();
+        g.set((1, 1), 5);
+        g.set((-1, 2), 7);
+        assert_eq!(
+            g.bounds(),
+            Some(Bounds {
+                min: (-1, 1),
+                max: (1, 2)
+            })
+        );
+        assert_eq!(g.get((1, 1)), Some(&5));
+        assert_eq!(g.get((0, 1)), Some(&0));
+        assert_eq!(g.get((2, 1)), None);
+        assert_eq!(
+            g.iter().filter(|(_, t)| **t != 0).collect::<Vec<_>>(),
+            vec![((1, 1), &5), ((-1, 2), &7)]
+        );
+    }
+
+    #[test]
+    fn dense_doubling() {
+        let mut g: DenseGrid<u16> = DenseGrid::new();
+        let mut layouts = 0;
+        for x in 0..1000 {
+            let before = g.alloc;
+            g.set((-x, x / 10), x as u16);
+            if g.alloc != before {
+                layouts += 1;
+            }
+        }
+        let b = g.bounds().unwrap();
+        assert_eq!((b.min, b.max), ((-999, 0), (0, 99)));
+        assert!(layouts < 20);
+        assert!(g.cells.len() < 4 * b.width() * b.height());
+        assert_eq!(g.values().count(), 1000 * 100);
+        assert_eq!(g.get((-500, 50)), Some(&500));
+        assert_eq!(g.get((-500, 49)), Some(&0));
+        assert_eq!(g.get((1, 0)), None);
+
+        let copy: DenseGrid<u16> = g.iter().map(|(p, t)| (p, *t)).collect();
+        assert_eq!(copy, g);
+        assert_eq!(copy.cells.len(), 1000 * 100);
+    }
+
+    impl Glyph for bool {
+        const ALL: &'static [bool] = &[true, false];
+
+        fn glyph(self) -> char {
+            if self {
+                '#'
+            } else {
+                '.'
+            }
+        }
+
+        fn name(self) -> &'static str {
+            if self {
+                "wall"
+            } else {
+                "open"
+            }
+        }
+    }
+
+    #[test]
+    fn map_file() {
+        let mut g = SparseGrid::parse(MAZE, wall);
+        g.set((-1, -2), false);
+        let text = g.to_text();
+        assert!(text.starts_with("legend\n# wall\n. open\norigin -1 -2\n.     \n"));
+        assert_eq!(SparseGrid::from_text(&text), Ok(g.clone()));
+
+        // redrawn with other characters, and no legend at all
+        let redrawn = "legend\nX wall\no open\norigin 0 0\nXXXXX\nXooXX\n";
+        assert_eq!(
+            SparseGrid::<bool>::from_text(redrawn)
+                .unwrap()
+                .render(false, |t| t.unwrap().glyph()),
+            "#####\n#..##\n"
+        );
+        let bare = SparseGrid::<bool>::from_text("origin 3 4\n.").unwrap();
+        assert_eq!(bare.get((3, 4)), Some(&false));
+
+        assert_eq!(
+            SparseGrid::<bool>::from_text("#"),
+            Err(Error::BadLegend("#".into()))
+        );
+        assert_eq!(
+            SparseGrid::<bool>::from_text("legend\n"),
+            Err(Error::NoOrigin)
+        );
+        assert_eq!(
+            SparseGrid::<bool>::from_text("origin 0 0\n#x"),
+            Err(Error::UnknownGlyph('x', (1, 0)))
+        );
+    }
+
+    #[test]
+    fn neighbors() {
+        assert_eq!(neighbors4((0, 0)).count(), 4);
+        assert!(neighbors8((3, 3)).all(|p| p != (3, 3)
+            && Bounds {
+                min: (2, 2),
+                max: (4, 4)
+            }
+            .contains(p)));
+    }
+}
@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+
+use crate::grid::{Grid, Point};
+
+/// Red, green, blue
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+
+/// Where the days write their pictures, when set. The extension picks the format, and any
+/// `{day}` is replaced by the number of the day writing it.
+pub const IMAGE_VAR: &str = "AOC_IMAGE";
+
+/// Colors overriding the days' own, by tile name: `wall=#808080,oxygen system=#00ffff`
+pub const PALETTE_VAR: &str = "AOC_PALETTE";
+
+/// Pixels per tile in exported pictures, 1 if unset
+pub const SCALE_VAR: &str = "AOC_IMAGE_SCALE";
+
+/// Raster picture, stored row by row from the top left
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    /// One pixel per cell of the grid's bounds, colored by `palette`. Rows run as
+    /// `Grid::render` draws them.
+    pub fn from_grid<T, G, F>(grid: &G, y_up: bool, palette: F) -> Self
+    where
+        G: Grid<T>,
+        F: Fn(Option<&T>) -> Rgb,
+    {
+        let b = match grid.bounds() {
+            Some(b) => b,
+            None => return Image::new(0, 0, BLACK),
+        };
+        let mut image = Image::new(b.width(), b.height(), BLACK);
+        for y in b.min.1..=b.max.1 {
+            let row = if y_up { b.max.1 - y } else { y - b.min.1 };
+            for x in b.min.0..=b.max.0 {
+                image.set(
+                    (x - b.min.0) as usize,
+                    row as usize,
+                    palette(grid.get((x, y))),
+                );
+            }
+        }
+        image
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Rgb> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Color a pixel. Pixels outside the picture are ignored.
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// Every pixel blown up into a `factor` by `factor` square
+    pub fn scaled(&self, factor: usize) -> Self {
+        let mut image = Image::new(self.width * factor, self.height * factor, BLACK);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                image.pixels[y * image.width + x] =
+                    self.pixels[y / factor * self.width + x / factor];
+            }
+        }
+        image
+    }
+
+    /// Binary PPM (P6)
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut buf = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        buf.extend(self.pixels.iter().flatten());
+        buf
+    }
+
+    /// Binary PGM (P5), each pixel reduced to its luma
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let mut buf = format!("P5\n{} {}\n255\n", self.width, self.height).into_bytes();
+        buf.extend(self.pixels.iter().map(|p| luma(*p)));
+        buf
+    }
+
+    /// 8-bit RGB PNG. The image data is stored rather than compressed, so no codec is needed.
+    /// PNG has no empty images, so one without a pixel each way is refused.
+    pub fn to_png(&self) -> io::Result<Vec<u8>> {
+        if self.width == 0 || self.height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot write a {}x{} PNG", self.width, self.height),
+            ));
+        }
+        let mut ihdr = Vec::new();
+        ihdr.extend(&(self.width as u32).to_be_bytes());
+        ihdr.extend(&(self.height as u32).to_be_bytes());
+        // bit depth 8, truecolor, deflate, adaptive filtering, no interlace
+        ihdr.extend(&[8, 2, 0, 0, 0]);
+
+        let mut raw = Vec::with_capacity(self.height * (self.width * 3 + 1));
+        for row in self.pixels.chunks(self.width) {
+            // filter type None
+            raw.push(0);
+            raw.extend(row.iter().flatten());
+        }
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        chunk(&mut png, b"IHDR", &ihdr);
+        chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        chunk(&mut png, b"IEND", &[]);
+        Ok(png)
+    }
+
+    /// SVG with each run of same-colored pixels in a row drawn as one `scale`-sized rectangle
+    pub fn to_svg(&self, scale: usize) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">\n",
+            self.width * scale,
+            self.height * scale,
+            self.width,
+            self.height
+        );
+        for y in 0..self.height {
+            let row = &self.pixels[y * self.width..(y + 1) * self.width];
+            let mut x = 0;
+            while x < row.len() {
+                let run = row[x..].iter().take_while(|p| **p == row[x]).count();
+                writeln!(
+                    svg,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"1\" fill=\"{}\"/>",
+                    x,
+                    y,
+                    run,
+                    hex(row[x])
+                )
+                .unwrap();
+                x += run;
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Write the picture in the format its extension names: `.ppm`, `.pgm`, `.png` or `.svg`
+    pub fn save(&self, path: &str, scale: usize) -> io::Result<()> {
+        let scale = scale.max(1);
+        match path.rsplit('.').next() {
+            Some("svg") => fs::write(path, self.to_svg(scale)),
+            Some("ppm") => fs::write(path, self.scaled(scale).to_ppm()),
+            Some("pgm") => fs::write(path, self.scaled(scale).to_pgm()),
+            Some("png") => fs::write(path, self.scaled(scale).to_png()?),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("no image format for {}", path),
+            )),
+        }
+    }
+}
+
+/// The path in `AOC_IMAGE` for `day`, if set
+fn export_path(day: u8) -> Option<String> {
+    env::var(IMAGE_VAR)
+        .ok()
+        .map(|path| path.replace("{day}", &day.to_string()))
+}
+
+/// Save `image` to the path in `AOC_IMAGE`, if set, blown up by `AOC_IMAGE_SCALE`
+pub fn export(day: u8, image: &Image) -> io::Result<()> {
+    match export_path(day) {
+        Some(path) => image.save(&path, export_scale()),
+        None => Ok(()),
+    }
+}
+
+/// Save an already drawn SVG to the path in `AOC_IMAGE`, if set
+pub fn export_svg(day: u8, svg: &str) -> io::Result<()> {
+    match export_path(day) {
+        Some(path) => save_svg(&path, svg),
+        None => Ok(()),
+    }
+}
+
+/// Write an already drawn SVG, refusing a path that names any other format
+fn save_svg(path: &str, svg: &str) -> io::Result<()> {
+    if path.rsplit('.').next() == Some("svg") {
+        fs::write(path, svg)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("this drawing is SVG only, not {}", path),
+        ))
+    }
+}
+
+fn export_scale() -> usize {
+    env::var(SCALE_VAR)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1)
+}
+
+/// SVG of each path as a line through its points, for drawings too large to rasterize
+pub fn paths_svg(paths: &[(Rgb, Vec<Point>)], y_up: bool) -> String {
+    let points = paths.iter().flat_map(|(_, p)| p.iter().cloned());
+    let b = match crate::grid::Bounds::of(points) {
+        Some(b) => b,
+        None => return "<svg xmlns=\"http://www.w3.org/2000/svg\"/>\n".to_string(),
+    };
+    // keep the stroke visible however far the drawing is zoomed out
+    let stroke = (b.width().max(b.height()) / 500).max(1);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        b.min.0,
+        if y_up { -b.max.1 } else { b.min.1 },
+        b.width(),
+        b.height()
+    );
+    for (color, path) in paths {
+        let coords = path
+            .iter()
+            .map(|p| format!("{},{}", p.0, if y_up { -p.1 } else { p.1 }))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            svg,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+            coords,
+            hex(*color),
+            stroke
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn hex(c: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2])
+}
+
+/// Colors picked by tile name, each falling back to the day's own choice
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Palette {
+    colors: HashMap<String, Rgb>,
+}
+
+impl Palette {
+    /// Read `name=#rrggbb` pairs separated by commas
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let bad = |entry: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("palette entry {:?} is not name=#rrggbb", entry),
+            )
+        };
+        let mut colors = HashMap::new();
+        for entry in text.split(',').filter(|e| !e.trim().is_empty()) {
+            let mut kv = entry.splitn(2, '=');
+            let name = kv.next().unwrap_or("").trim();
+            let color = kv
+                .next()
+                .and_then(|c| c.trim().strip_prefix('#'))
+                .filter(|c| c.len() == 6)
+                .and_then(|c| u32::from_str_radix(c, 16).ok())
+                .ok_or_else(|| bad(entry))?;
+            if name.is_empty() {
+                return Err(bad(entry));
+            }
+            colors.insert(
+                name.to_string(),
+                [(color >> 16) as u8, (color >> 8) as u8, color as u8],
+            );
+        }
+        Ok(Palette { colors })
+    }
+
+    /// The palette in `AOC_PALETTE`, or an empty one if it is unset
+    pub fn from_env() -> io::Result<Self> {
+        match env::var(PALETTE_VAR) {
+            Ok(text) => Palette::parse(&text),
+            Err(_) => Ok(Palette::default()),
+        }
+    }
+
+    /// The color set for `name`, or `default`
+    pub fn color(&self, name: &str, default: Rgb) -> Rgb {
+        self.colors.get(name).copied().unwrap_or(default)
+    }
+}
+
+fn luma(c: Rgb) -> u8 {
+    ((299 * c[0] as u32 + 587 * c[1] as u32 + 114 * c[2] as u32) / 1000) as u8
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(&crc.to_be_bytes());
+}
+
+/// zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // deflate, 32K window, no dictionary, fastest
+    let mut z = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        z.extend(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        z.push(blocks.peek().is_none() as u8);
+        let len = block.len() as u16;
+        z.extend(&len.to_le_bytes());
+        z.extend(&(!len).to_le_bytes());
+        z.extend(block);
+    }
+    z.extend(&adler32(data).to_be_bytes());
+    z
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for d in data {
+        a = (a + *d as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::SparseGrid;
+
+    fn checker() -> Image {
+        let mut image = Image::new(2, 2, BLACK);
+        image.set(0, 0, WHITE);
+        image.set(1, 1, WHITE);
+        image
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn netpbm() {
+        let image = checker();
+        assert_eq!(
+            image.to_ppm(),
+            b"P6\n2 2\n255\n\xff\xff\xff\0\0\0\0\0\0\xff\xff\xff".to_vec()
+        );
+        assert_eq!(image.to_pgm(), b"P5\n2 2\n255\n\xff\0\0\xff".to_vec());
+    }
+
+    #[test]
+    fn png() {
+        let png = checker().to_png().unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 2, 0, 0, 0, 2]);
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+
+        // two filtered rows of two pixels in one final stored block
+        let idat = &png[33 + 8..png.len() - 12 - 4];
+        assert_eq!(&idat[..7], &[0x78, 0x01, 1, 14, 0, !14, 0xff]);
+        assert_eq!(&idat[7..21], b"\0\xff\xff\xff\0\0\0\0\0\0\0\xff\xff\xff");
+
+        for (w, h) in &[(0, 3), (3, 0), (0, 0)] {
+            let err = Image::new(*w, *h, BLACK).to_png().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn svg_only() {
+        let path = std::env::temp_dir().join("aoc2019_svg_only.png");
+        let err = save_svg(path.to_str().unwrap(), "<svg/>").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn palette() {
+        let p = Palette::parse("wall=#808080, oxygen system=#00FFff").unwrap();
+        assert_eq!(p.color("wall", BLACK), [128, 128, 128]);
+        assert_eq!(p.color("oxygen system", BLACK), [0, 255, 255]);
+        assert_eq!(p.color("open", WHITE), WHITE);
+        assert_eq!(Palette::parse("").unwrap(), Palette::default());
+        for bad in &[
+            "wall",
+            "wall=808080",
+            "wall=#8080",
+            "=#808080",
+            "wall=#80808g",
+        ] {
+            assert!(Palette::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn scale_and_svg() {
+        let image = checker().scaled(2);
+        assert_eq!((image.width(), image.height()), (4, 4));
+        assert_eq!(image.get(1, 1), Some(WHITE));
+        assert_eq!(image.get(2, 1), Some(BLACK));
+        assert_eq!(image.get(3, 3), Some(WHITE));
+
+        let svg = checker().to_svg(10);
+        assert!(svg.contains("width=\"20\" height=\"20\" viewBox=\"0 0 2 2\""));
+        assert_eq!(svg.matches("<rect").count(), 4);
+    }
+
+    #[test]
+    fn grids() {
+        let g: SparseGrid<bool> = vec![((0, 0), true), ((1, 1), false)].into_iter().collect();
+        let palette = |t: Option<&bool>| match t {
+            Some(true) => WHITE,
+            Some(false) => [255, 0, 0],
+            None => BLACK,
+        };
+        let down = Image::from_grid(&g, false, palette);
+        assert_eq!(down.get(0, 0), Some(WHITE));
+        assert_eq!(down.get(1, 1), Some([255, 0, 0]));
+        let up = Image::from_grid(&g, true, palette);
+        assert_eq!(up.get(0, 1), Some(WHITE));
+        assert_eq!(up.get(1, 0), Some([255, 0, 0]));
+    }
+}
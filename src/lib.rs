@@ -39,3 +39,24 @@ pub mod day13;
 
 /// AoC Day 15
 pub mod day15;
+
+/// AoC Day 25
+pub mod day25;
+
+/// Terminal animation and asciicast recording
+pub mod cast;
+
+/// Intcode programs driving hardware through a fixed I/O protocol
+pub mod device;
+
+/// 2D maps shared by the grid days
+pub mod grid;
+
+/// Picture export for the 2D results
+pub mod image;
+
+/// Reading the block capitals some puzzles draw
+pub mod ocr;
+
+/// Shortest paths and flood fills over grids and graphs
+pub mod search;
@@ -5,8 +5,12 @@ use std::env::args;
 fn main() {
     env_logger::init();
 
-    println!("AOC 2019");
-    match args().nth(1).expect("usage: aoc2019 <num>").as_str() {
+    let day = args().nth(1).expect("usage: aoc2019 <num>");
+    // these print files, so keep stdout clean for redirecting
+    if !["aot", "diff", "patch"].contains(&day.as_str()) {
+        println!("AOC 2019");
+    }
+    match day.as_str() {
         "1" => println!(
             "day 1: {}",
             day1::run().unwrap_or_else(|e| format!("failure: {}", e))
@@ -55,10 +59,82 @@ fn main() {
             "day 13: {}",
             day13::run().unwrap_or_else(|e| format!("failure: {:?}", e))
         ),
+        "13-play" => {
+            let tick = args()
+                .nth(2)
+                .map(|ms| std::time::Duration::from_millis(ms.parse().expect("tick in ms")));
+            let score = day13::play(tick);
+            println!(
+                "day 13: {}",
+                score.map_or_else(|e| format!("failure: {:?}", e), |s| s.to_string())
+            );
+        }
+        "13-replay" => println!(
+            "day 13: {}",
+            day13::watch(&args().nth(2).expect("usage: aoc2019 13-replay <file>"))
+                .map_or_else(|e| format!("failure: {:?}", e), |s| s.to_string())
+        ),
         "15" => println!(
             "day 15: {}",
             day15::run().unwrap_or_else(|e| format!("failure: {:?}", e))
         ),
+        "15-walk" => println!(
+            "day 15: {}",
+            day15::walk().map_or_else(|e| format!("failure: {:?}", e), |s| s.to_string())
+        ),
+        "15-map" => println!(
+            "day 15: {}",
+            day15::run_map(&args().nth(2).expect("usage: aoc2019 15-map <map file>"))
+                .unwrap_or_else(|e| format!("failure: {:?}", e))
+        ),
+        "25" => println!(
+            "day 25: {}",
+            day25::run().unwrap_or_else(|e| format!("failure: {:?}", e))
+        ),
+        "25i" => {
+            if let Err(e) = day25::play() {
+                println!("day 25: failure: {:?}", e);
+            }
+        }
+        "diff" => {
+            let file = |n| {
+                day2::read_comma_file(&args().nth(n).expect("usage: aoc2019 diff <base> <target>"))
+            };
+            match file(2).and_then(|base| Ok(day2::Patch::diff(&base, &file(3)?))) {
+                Ok(patch) => print!("{}", patch),
+                Err(e) => fail(&day, e),
+            }
+        }
+        "patch" => match day2::read_patched(
+            &args()
+                .nth(2)
+                .expect("usage: aoc2019 patch <program> <patch>"),
+            &args()
+                .nth(3)
+                .expect("usage: aoc2019 patch <program> <patch>"),
+        ) {
+            Ok(mem) => println!(
+                "{}",
+                mem.iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            Err(e) => fail(&day, e),
+        },
+        "aot" => match day2::transpile::transpile_file(
+            &args().nth(3).unwrap_or_else(|| "program".to_string()),
+            &args().nth(2).expect("usage: aoc2019 aot <file> [fn name]"),
+        ) {
+            Ok(source) => print!("{}", source),
+            Err(e) => fail(&day, e),
+        },
         _ => unimplemented!(),
     }
 }
+
+/// Report a failure of one of the file-printing commands on stderr, leaving stdout empty
+fn fail(command: &str, e: day2::Error) {
+    eprintln!("{}: failure: {:?}", command, e);
+    std::process::exit(1);
+}
@@ -0,0 +1,292 @@
+use crate::grid::Grid;
+
+/// Capital letters 6 pixels tall, as drawn by the day 8 and day 11 puzzles
+const FONT6: &[(char, [&str; 6])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// The larger 10 pixel tall capitals some other years use
+const FONT10: &[(char, [&str; 10])] = &[
+    (
+        'A',
+        [
+            "..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'B',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#",
+            "#....#", "#####.",
+        ],
+    ),
+    (
+        'C',
+        [
+            ".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#....#", ".####.",
+        ],
+    ),
+    (
+        'E',
+        [
+            "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+    (
+        'F',
+        [
+            "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "#.....",
+        ],
+    ),
+    (
+        'G',
+        [
+            ".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#",
+            "#...##", ".###.#",
+        ],
+    ),
+    (
+        'H',
+        [
+            "#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'J',
+        [
+            "...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.",
+            "#...#.", ".###..",
+        ],
+    ),
+    (
+        'K',
+        [
+            "#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..",
+            "#...#.", "#....#",
+        ],
+    ),
+    (
+        'L',
+        [
+            "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+    (
+        'N',
+        [
+            "#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##",
+            "#...##", "#....#",
+        ],
+    ),
+    (
+        'P',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "#.....",
+        ],
+    ),
+    (
+        'R',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'X',
+        [
+            "#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'Z',
+        [
+            "######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Nothing is lit
+    Blank,
+    /// Lit rows span a height neither font is drawn at
+    BadHeight(usize),
+    /// Glyph starting at this column is not in the font
+    UnknownGlyph(usize),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
+
+fn matches(glyph: &[&[bool]], letter: &[&str]) -> bool {
+    glyph.len() == letter.len()
+        && glyph.iter().zip(letter).all(|(row, pattern)| {
+            row.len() == pattern.len()
+                && row
+                    .iter()
+                    .zip(pattern.bytes())
+                    .all(|(p, c)| *p == (c == b'#'))
+        })
+}
+
+/// Read a line of block capitals. Blank rows around the text are ignored, and letters are
+/// told apart by the blank columns between them.
+pub fn recognize(pixels: &[Vec<bool>]) -> Result<String, Error> {
+    let lit_rows = pixels
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| row.contains(&true))
+        .map(|(y, _)| y)
+        .collect::<Vec<_>>();
+    let rows = match (lit_rows.first(), lit_rows.last()) {
+        (Some(top), Some(bottom)) => &pixels[*top..=*bottom],
+        _ => return Err(Error::Blank),
+    };
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let lit = |x: usize, y: usize| rows[y].get(x).cloned().unwrap_or(false);
+    let blank_column = |x: usize| (0..rows.len()).all(|y| !lit(x, y));
+
+    let mut text = String::new();
+    let mut x = 0;
+    while x < width {
+        if blank_column(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && !blank_column(x) {
+            x += 1;
+        }
+        let glyph = rows
+            .iter()
+            .map(|row| row.get(start..x.min(row.len())).unwrap_or(&[]))
+            .collect::<Vec<_>>();
+        let letter = match rows.len() {
+            6 => FONT6
+                .iter()
+                .find(|(_, pattern)| matches(&glyph, pattern))
+                .map(|(c, _)| *c),
+            10 => FONT10
+                .iter()
+                .find(|(_, pattern)| matches(&glyph, pattern))
+                .map(|(c, _)| *c),
+            h => return Err(Error::BadHeight(h)),
+        };
+        text.push(letter.ok_or(Error::UnknownGlyph(start))?);
+    }
+    Ok(text)
+}
+
+/// Read the block capitals drawn on a grid, with rows ordered as `Grid::render` would draw them
+pub fn read<T, G, F>(grid: &G, y_up: bool, lit: F) -> Result<String, Error>
+where
+    G: Grid<T>,
+    F: Fn(Option<&T>) -> bool,
+{
+    let b = grid.bounds().ok_or(Error::Blank)?;
+    let mut rows: Vec<i64> = (b.min.1..=b.max.1).collect();
+    if y_up {
+        rows.reverse();
+    }
+    let pixels = rows
+        .into_iter()
+        .map(|y| (b.min.0..=b.max.0).map(|x| lit(grid.get((x, y)))).collect())
+        .collect::<Vec<Vec<bool>>>();
+    recognize(&pixels)
+}
+
+/// Read block capitals from text, where `lit` picks out the characters that are drawn
+pub fn read_text<F: Fn(char) -> bool>(text: &str, lit: F) -> Result<String, Error> {
+    let pixels = text
+        .lines()
+        .map(|line| line.chars().map(&lit).collect())
+        .collect::<Vec<Vec<bool>>>();
+    recognize(&pixels)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::SparseGrid;
+
+    fn draw(letters: &[&[&str]], gap: usize) -> String {
+        (0..letters[0].len())
+            .map(|y| {
+                letters
+                    .iter()
+                    .map(|l| l[y].to_string())
+                    .collect::<Vec<_>>()
+                    .join(&".".repeat(gap))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn whole_font() {
+        let small = FONT6.iter().map(|(_, l)| &l[..]).collect::<Vec<_>>();
+        let expected = FONT6.iter().map(|(c, _)| *c).collect::<String>();
+        assert_eq!(read_text(&draw(&small, 1), |c| c == '#'), Ok(expected));
+
+        let large = FONT10.iter().map(|(_, l)| &l[..]).collect::<Vec<_>>();
+        let expected = FONT10.iter().map(|(c, _)| *c).collect::<String>();
+        assert_eq!(read_text(&draw(&large, 2), |c| c == '#'), Ok(expected));
+    }
+
+    #[test]
+    fn padding() {
+        let text = "\n......\n.####.\n.#....\n.###..\n.#....\n.#....\n.####.\n......\n";
+        assert_eq!(read_text(text, |c| c == '#'), Ok("E".to_string()));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(read_text("...\n...", |c| c == '#'), Err(Error::Blank));
+        assert_eq!(read_text("#\n#", |c| c == '#'), Err(Error::BadHeight(2)));
+        let text = "####.#\n#..#.#\n#..#.#\n#..#.#\n#..#.#\n####.#";
+        assert_eq!(read_text(text, |c| c == '#'), Err(Error::UnknownGlyph(0)));
+    }
+
+    #[test]
+    fn grid() {
+        let g = SparseGrid::parse(&draw(&[&FONT6[7].1], 0), |c| {
+            if c == '#' {
+                Some(())
+            } else {
+                None
+            }
+        });
+        assert_eq!(read(&g, false, |t| t.is_some()), Ok("I".to_string()));
+    }
+}
@@ -0,0 +1,317 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use crate::grid::{Grid, Point};
+
+/// Every node a search reached, with its distance from the nearest source and the node it was
+/// reached from
+#[derive(Clone, Debug)]
+pub struct Paths<N> {
+    reached: HashMap<N, (usize, Option<N>)>,
+}
+
+impl<N: Hash + Eq + Clone> Paths<N> {
+    fn new() -> Self {
+        Paths {
+            reached: HashMap::new(),
+        }
+    }
+
+    pub fn distance(&self, node: &N) -> Option<usize> {
+        self.reached.get(node).map(|(d, _)| *d)
+    }
+
+    /// The route from a source to `node`, both ends included
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        let mut path = vec![node.clone()];
+        let mut prev = &self.reached.get(node)?.1;
+        while let Some(p) = prev {
+            path.push(p.clone());
+            prev = &self.reached[p].1;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Each reached node and its distance, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&N, usize)> {
+        self.reached.iter().map(|(n, (d, _))| (n, *d))
+    }
+
+    /// Distance to the node farthest from every source, such as the minutes a flood takes
+    pub fn farthest(&self) -> Option<usize> {
+        self.reached.values().map(|(d, _)| *d).max()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reached.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reached.is_empty()
+    }
+}
+
+/// Breadth first from every source at once, counting each edge as one step
+pub fn flood<N, F, I>(sources: impl IntoIterator<Item = N>, mut neighbors: F) -> Paths<N>
+where
+    N: Hash + Eq + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    let mut paths = Paths::new();
+    let mut queue = VecDeque::new();
+    for s in sources {
+        paths.reached.insert(s.clone(), (0, None));
+        queue.push_back((s, 0));
+    }
+    while let Some((node, d)) = queue.pop_front() {
+        for n in neighbors(&node) {
+            if !paths.reached.contains_key(&n) {
+                paths.reached.insert(n.clone(), (d + 1, Some(node.clone())));
+                queue.push_back((n, d + 1));
+            }
+        }
+    }
+    paths
+}
+
+/// Breadth first distances from `start` to everything it reaches
+pub fn bfs<N, F, I>(start: N, neighbors: F) -> Paths<N>
+where
+    N: Hash + Eq + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    flood(Some(start), neighbors)
+}
+
+/// The fewest-steps route from `start` to `goal`, both ends included. Stops searching once
+/// `goal` is reached.
+pub fn bfs_to<N, F, I>(start: N, goal: &N, mut neighbors: F) -> Option<Vec<N>>
+where
+    N: Hash + Eq + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    let mut paths = Paths::new();
+    let mut queue = VecDeque::new();
+    paths.reached.insert(start.clone(), (0, None));
+    queue.push_back((start, 0));
+    while let Some((node, d)) = queue.pop_front() {
+        if node == *goal {
+            return paths.path_to(goal);
+        }
+        for n in neighbors(&node) {
+            if !paths.reached.contains_key(&n) {
+                paths.reached.insert(n.clone(), (d + 1, Some(node.clone())));
+                queue.push_back((n, d + 1));
+            }
+        }
+    }
+    None
+}
+
+/// Cheapest-first search with an estimate of the cost left from each node, which must never
+/// overestimate. Settles nodes until `goal` is, or until none are left.
+fn best_first<N, F, I, H>(start: N, goal: Option<&N>, mut neighbors: F, estimate: H) -> Paths<N>
+where
+    N: Hash + Eq + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+    H: Fn(&N) -> usize,
+{
+    let mut paths = Paths::new();
+    // the heap holds indices into `nodes`, so nodes need not be ordered
+    let mut nodes = vec![start.clone()];
+    let mut heap = BinaryHeap::new();
+    let mut settled = HashSet::new();
+    paths.reached.insert(start.clone(), (0, None));
+    heap.push(Reverse((estimate(&start), 0, 0)));
+
+    while let Some(Reverse((_, cost, i))) = heap.pop() {
+        let node = nodes[i].clone();
+        // later, costlier entries for a node that is already settled
+        if !settled.insert(node.clone()) {
+            continue;
+        }
+        if Some(&node) == goal {
+            break;
+        }
+        for (n, weight) in neighbors(&node) {
+            let d = cost + weight;
+            if paths.distance(&n).is_none_or(|old| d < old) {
+                paths.reached.insert(n.clone(), (d, Some(node.clone())));
+                heap.push(Reverse((d + estimate(&n), d, nodes.len())));
+                nodes.push(n);
+            }
+        }
+    }
+    paths
+}
+
+/// Cheapest distances from `start` over edges weighted by `neighbors`
+pub fn dijkstra<N, F, I>(start: N, neighbors: F) -> Paths<N>
+where
+    N: Hash + Eq + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+{
+    best_first(start, None, neighbors, |_| 0)
+}
+
+/// The cheapest route from `start` to `goal` and its cost, guided by `heuristic`, an estimate of
+/// the cost left that never overestimates it, such as `manhattan` on a grid
+pub fn astar<N, F, I, H>(start: N, goal: &N, neighbors: F, heuristic: H) -> Option<(usize, Vec<N>)>
+where
+    N: Hash + Eq + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+    H: Fn(&N) -> usize,
+{
+    let paths = best_first(start, Some(goal), neighbors, heuristic);
+    Some((paths.distance(goal)?, paths.path_to(goal)?))
+}
+
+pub fn manhattan(a: Point, b: Point) -> usize {
+    ((a.0 - b.0).abs() + (a.1 - b.1).abs()) as usize
+}
+
+/// The horizontal and vertical neighbors of a grid cell whose tiles `open` lets through
+pub fn grid_neighbors<'a, T, G, F>(grid: &'a G, open: F) -> impl Fn(&Point) -> Vec<Point> + 'a
+where
+    G: Grid<T>,
+    F: Fn(&T) -> bool + 'a,
+    T: 'a,
+{
+    move |p| {
+        grid.around4(*p)
+            .into_iter()
+            .filter(|(_, t)| open(t))
+            .map(|(n, _)| n)
+            .collect()
+    }
+}
+
+/// Like `grid_neighbors`, with each step costing one, for `dijkstra` and `astar`
+pub fn grid_steps<'a, T, G, F>(grid: &'a G, open: F) -> impl Fn(&Point) -> Vec<(Point, usize)> + 'a
+where
+    G: Grid<T>,
+    F: Fn(&T) -> bool + 'a,
+    T: 'a,
+{
+    let neighbors = grid_neighbors(grid, open);
+    move |p| neighbors(p).into_iter().map(|n| (n, 1)).collect()
+}
+
+/// Each node's children and parent, from a map of children such as the day 6 orbits, so the tree
+/// can be searched in any direction
+pub fn tree_neighbors<N>(children: &HashMap<N, Vec<N>>) -> impl Fn(&N) -> Vec<N>
+where
+    N: Hash + Eq + Clone,
+{
+    let mut edges: HashMap<N, Vec<N>> = HashMap::new();
+    for (parent, kids) in children {
+        for kid in kids {
+            edges.entry(parent.clone()).or_default().push(kid.clone());
+            edges.entry(kid.clone()).or_default().push(parent.clone());
+        }
+    }
+    move |n| edges.get(n).cloned().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::SparseGrid;
+
+    const MAZE: &str = "\
+#########
+#S..#...#
+#.#.#.#.#
+#.#...#G#
+#########
+";
+
+    fn maze() -> SparseGrid<char> {
+        SparseGrid::parse(MAZE, Some)
+    }
+
+    fn open(c: &char) -> bool {
+        *c != '#'
+    }
+
+    #[test]
+    fn breadth_first() {
+        let grid = maze();
+        let paths = bfs((1, 1), grid_neighbors(&grid, open));
+        assert_eq!(paths.distance(&(7, 3)), Some(12));
+        assert_eq!(paths.len(), 15);
+        assert_eq!(paths.farthest(), Some(12));
+        let path = paths.path_to(&(7, 3)).unwrap();
+        assert_eq!(path.len(), 13);
+        assert_eq!(path.first(), Some(&(1, 1)));
+        assert_eq!(
+            bfs_to((1, 1), &(7, 3), grid_neighbors(&grid, open)),
+            Some(path)
+        );
+        assert_eq!(bfs_to((1, 1), &(0, 0), grid_neighbors(&grid, open)), None);
+    }
+
+    #[test]
+    fn multi_source() {
+        let grid = maze();
+        let paths = flood(vec![(1, 1), (7, 3)], grid_neighbors(&grid, open));
+        assert_eq!(paths.distance(&(7, 3)), Some(0));
+        assert_eq!(paths.farthest(), Some(6));
+    }
+
+    #[test]
+    fn weighted() {
+        // the direct edge costs more than the detour
+        let edges: HashMap<char, Vec<(char, usize)>> = vec![
+            ('a', vec![('b', 7), ('c', 2)]),
+            ('c', vec![('b', 3), ('d', 9)]),
+            ('b', vec![('d', 1)]),
+        ]
+        .into_iter()
+        .collect();
+        let next = |n: &char| edges.get(n).cloned().unwrap_or_default();
+        let paths = dijkstra('a', next);
+        assert_eq!(paths.distance(&'b'), Some(5));
+        assert_eq!(paths.distance(&'d'), Some(6));
+        assert_eq!(paths.path_to(&'d'), Some(vec!['a', 'c', 'b', 'd']));
+        assert_eq!(
+            astar('a', &'d', next, |_| 0),
+            Some((6, vec!['a', 'c', 'b', 'd']))
+        );
+        assert_eq!(astar('d', &'a', next, |_| 0), None);
+    }
+
+    #[test]
+    fn a_star() {
+        let grid = maze();
+        let goal = (7, 3);
+        let (cost, path) = astar((1, 1), &goal, grid_steps(&grid, open), |p| {
+            manhattan(*p, goal)
+        })
+        .unwrap();
+        assert_eq!(cost, 12);
+        assert_eq!(path.len(), 13);
+        assert_eq!(
+            dijkstra((1, 1), grid_steps(&grid, open)).distance(&goal),
+            Some(cost)
+        );
+    }
+
+    #[test]
+    fn tree() {
+        let children: HashMap<&str, Vec<&str>> =
+            vec![("COM", vec!["B"]), ("B", vec!["C", "G"]), ("C", vec!["D"])]
+                .into_iter()
+                .collect();
+        let path = bfs_to("D", &"G", tree_neighbors(&children));
+        assert_eq!(path, Some(vec!["D", "C", "B", "G"]));
+    }
+}